@@ -0,0 +1,144 @@
+use coe::{coerce_copy, coerce_static, is_same};
+use core::any::TypeId;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+#[inline(always)]
+fn is_same_always<T: 'static, U: 'static>() -> bool {
+    TypeId::of::<T>() == TypeId::of::<U>()
+}
+
+macro_rules! dispatch {
+    ($name: ident, $check: ident, $($ty: ty),* $(,)?) => {
+        #[inline(never)]
+        fn $name<T: 'static>(x: u64) -> u64 {
+            let mut i = 0u64;
+            $(
+                i += 1;
+                if $check::<T, $ty>() {
+                    return x.wrapping_mul(i);
+                }
+            )*
+            x
+        }
+    };
+}
+
+macro_rules! dispatch_both {
+    ($($ty: ty),* $(,)?) => {
+        dispatch!(dispatch_inline, is_same, $($ty),*);
+        dispatch!(dispatch_inline_always, is_same_always, $($ty),*);
+    };
+}
+
+dispatch_both!(
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    bool,
+    char,
+    (),
+    (u8,),
+    (u16,),
+    (u32,),
+    (u64,),
+    (f32,),
+    (f64,),
+    [u8; 1],
+    [u16; 1],
+    [u32; 1],
+    [u64; 1],
+    [f32; 1],
+    [f64; 1],
+    &'static str,
+);
+
+fn bench_dispatch(c: &mut Criterion) {
+    c.bench_function("dispatch_30_arms/inline", |b| {
+        b.iter(|| {
+            dispatch_inline::<&'static str>(black_box(3))
+                ^ dispatch_inline::<u8>(black_box(3))
+                ^ dispatch_inline::<i128>(black_box(3))
+        })
+    });
+    c.bench_function("dispatch_30_arms/inline_always", |b| {
+        b.iter(|| {
+            dispatch_inline_always::<&'static str>(black_box(3))
+                ^ dispatch_inline_always::<u8>(black_box(3))
+                ^ dispatch_inline_always::<i128>(black_box(3))
+        })
+    });
+}
+
+#[inline(never)]
+fn scale_static<T: 'static + Copy>(x: T) -> T {
+    let x: f64 = coerce_static(x);
+    coerce_static(x * 2.0)
+}
+
+#[inline(never)]
+fn scale_copy<T: 'static + Copy>(x: T) -> T {
+    let x: f64 = coerce_copy(x);
+    coerce_copy(x * 2.0)
+}
+
+#[inline(never)]
+fn scale_array_static<T: 'static + Copy>(x: T) -> T {
+    let x: [f64; 8] = coerce_static(x);
+    coerce_static(x.map(|x| x * 2.0))
+}
+
+#[inline(never)]
+fn scale_array_copy<T: 'static + Copy>(x: T) -> T {
+    let x: [f64; 8] = coerce_copy(x);
+    coerce_copy(x.map(|x| x * 2.0))
+}
+
+fn bench_copy(c: &mut Criterion) {
+    c.bench_function("coerce_f64/coerce_static", |b| {
+        b.iter(|| scale_static(black_box(1.5f64)))
+    });
+    c.bench_function("coerce_f64/coerce_copy", |b| {
+        b.iter(|| scale_copy(black_box(1.5f64)))
+    });
+    c.bench_function("coerce_f64x8/coerce_static", |b| {
+        b.iter(|| scale_array_static(black_box([1.5f64; 8])))
+    });
+    c.bench_function("coerce_f64x8/coerce_copy", |b| {
+        b.iter(|| scale_array_copy(black_box([1.5f64; 8])))
+    });
+}
+
+#[inline(never)]
+fn identity(x: [f64; 8]) -> [f64; 8] {
+    x
+}
+
+#[inline(never)]
+fn identity_coerced<T: 'static>(x: T) -> [f64; 8] {
+    coerce_static(x)
+}
+
+// both functions compile to the same code, since the mismatch panic is out of line and the
+// comparison folds away, which `tests/hot_path_size.rs` checks.
+fn bench_hot_path(c: &mut Criterion) {
+    c.bench_function("coerce_static_hot_path/identity", |b| {
+        b.iter(|| identity(black_box([1.5f64; 8])))
+    });
+    c.bench_function("coerce_static_hot_path/coerce_static", |b| {
+        b.iter(|| identity_coerced(black_box([1.5f64; 8])))
+    });
+}
+
+criterion_group!(benches, bench_dispatch, bench_copy, bench_hot_path);
+criterion_main!(benches);
@@ -0,0 +1,155 @@
+//! Derive macro for the `Coerce` trait of `coe-rs`. See the documentation of `coe::Coerce`.
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::visit_mut::VisitMut;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, GenericParam, Ident, Type};
+
+/// Renames the type parameters of the input into their coerced counterparts.
+struct Rename<'a>(&'a [(Ident, Ident)]);
+
+impl Rename<'_> {
+    fn get(&self, ident: &Ident) -> Option<&Ident> {
+        self.0
+            .iter()
+            .find(|(src, _)| src == ident)
+            .map(|(_, dst)| dst)
+    }
+}
+
+impl VisitMut for Rename<'_> {
+    fn visit_type_path_mut(&mut self, ty: &mut syn::TypePath) {
+        if ty.qself.is_none() && ty.path.segments.len() == 1 {
+            let segment = &mut ty.path.segments[0];
+            if segment.arguments.is_empty() {
+                if let Some(dst) = self.get(&segment.ident) {
+                    segment.ident = dst.clone();
+                    return;
+                }
+            }
+        }
+        syn::visit_mut::visit_type_path_mut(self, ty);
+    }
+}
+
+/// Returns `true` if the type is a slice or `str`, making the struct unsized.
+fn is_unsized(ty: &Type) -> bool {
+    match ty {
+        Type::Slice(_) => true,
+        Type::Path(path) => path.qself.is_none() && path.path.is_ident("str"),
+        _ => false,
+    }
+}
+
+/// Derives `coe::Coerce` for a struct, coercing it into the same struct with different type
+/// parameters, provided each pair of type parameters is the same type.
+///
+/// Sized structs coerce by value. Structs whose last field is a slice or `str` need no impl, since
+/// references to them already coerce through the blanket reference impls, and derive nothing.
+#[proc_macro_derive(Coerce)]
+pub fn derive_coerce(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let last_field = match &input.data {
+        Data::Struct(data) => data.fields.iter().next_back(),
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "`Coerce` can only be derived for structs",
+            ))
+        }
+    };
+    // references to unsized structs already coerce through the blanket reference impls.
+    if last_field.is_some_and(|field| is_unsized(&field.ty)) {
+        return Ok(TokenStream::new());
+    }
+
+    let renames: Vec<(Ident, Ident)> = input
+        .generics
+        .type_params()
+        .map(|param| {
+            let dst = Ident::new(&format!("__Coerce{}", param.ident), Span::call_site());
+            (param.ident.clone(), dst)
+        })
+        .collect();
+    if renames.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "`Coerce` can only be derived for structs with type parameters",
+        ));
+    }
+    let mut rename = Rename(&renames);
+
+    let mut generics = input.generics.clone();
+    let mut dst_params = Vec::new();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!('static));
+        param.default = None;
+        let mut dst = param.clone();
+        rename.visit_type_param_mut(&mut dst);
+        dst.ident = rename.get(&param.ident).unwrap().clone();
+        dst_params.push(GenericParam::Type(dst));
+    }
+    generics.params.extend(dst_params);
+    if let Some(where_clause) = &input.generics.where_clause {
+        let mut predicates = where_clause.predicates.clone();
+        for predicate in &mut predicates {
+            rename.visit_where_predicate_mut(predicate);
+        }
+        generics.make_where_clause().predicates.extend(predicates);
+    }
+
+    let ident = &input.ident;
+    let src_args = input.generics.params.iter().map(|param| match param {
+        GenericParam::Lifetime(param) => {
+            let lifetime = &param.lifetime;
+            quote!(#lifetime)
+        }
+        GenericParam::Type(param) => {
+            let ident = &param.ident;
+            quote!(#ident)
+        }
+        GenericParam::Const(param) => {
+            let ident = &param.ident;
+            quote!(#ident)
+        }
+    });
+    let dst_args = input.generics.params.iter().map(|param| match param {
+        GenericParam::Lifetime(param) => {
+            let lifetime = &param.lifetime;
+            quote!(#lifetime)
+        }
+        GenericParam::Type(param) => {
+            let dst = rename.get(&param.ident).unwrap();
+            quote!(#dst)
+        }
+        GenericParam::Const(param) => {
+            let ident = &param.ident;
+            quote!(#ident)
+        }
+    });
+    let src = quote!(#ident<#(#src_args),*>);
+    let dst = quote!(#ident<#(#dst_args),*>);
+    let asserts: Vec<_> = renames
+        .iter()
+        .map(|(src, dst)| quote!(::coe::assert_same::<#src, #dst>();))
+        .collect();
+
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::coe::Coerce<#dst> for #src #where_clause {
+            ::coe::__coerce_inline! {
+                #[track_caller]
+                fn coerce(self) -> #dst {
+                    #(#asserts)*
+                    unsafe { ::coe::coerce_unchecked(self) }
+                }
+            }
+        }
+    })
+}
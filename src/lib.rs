@@ -1,27 +1,418 @@
 //! `coe-rs` is a Rust library for coercing a value of a given type into the same type, in cases
 //! where the compiler can't prove the two types are equal.  
 //! This can be used to emulate specialization in to a limited extent.
+//!
+//! # Features
+//! - `alloc`: enables coercion of `alloc` types such as `Box`, `Vec`, `Rc` and `Arc`.
+//! - `std`: implies `alloc`.
+//! - `specialization_guard`: implies `std`, and enables `SpecializationGuard`, which records
+//!   checked coercions in a thread local to assert in tests that a specialized branch was taken.
+//!   This adds a call to every checked coercion, so it's only meant for tests.
+//! - `nonstatic`: enables `is_same_nonstatic`, which compares types that aren't `'static`.
+//! - `const_typeid`: enables `is_same_const`. Requires a nightly compiler.
+//! - `derive`: enables `#[derive(Coerce)]` for structs with type parameters.
+//! - `log`: emits a `log::trace!` record naming both types on every checked coercion, to help
+//!   find out which specialization branch is taken.
+//! - `minimal_panic`: makes failed type assertions panic with a fixed `"type mismatch"` message
+//!   instead of formatting the names of both types, which avoids pulling in the formatting
+//!   machinery on size-constrained targets, e.g. when building with `panic_immediate_abort`.
+//! - `no_inline_always`: downgrades the `#[inline(always)]` hints on the coercion functions to
+//!   `#[inline]`. The forced inlining lets the type checks fold away at every call site, which is
+//!   what SIMD dispatch relies on, but it duplicates the coercion code at each of them. Enabling
+//!   this feature lets the optimizer decide instead, which can noticeably reduce code size when
+//!   `coerce` is called in many places, e.g. on embedded targets.
+//!
+//! # Panic-free usage
+//! [`Coerce`] and [`coerce_static`] panic when the types don't match. Code that must not unwind,
+//! e.g. in interrupt handlers, can stick to the fallible surface instead, which returns the
+//! original value on mismatch and contains no panics, not even debug assertions: [`TryCoerce`],
+//! [`try_coerce_static`], [`coerce_static_catch`], [`coerce_static_checked`] and [`check_same`].
+//! This is a subset rather than a feature, since a feature removing the panicking API would break
+//! other crates depending on `coe-rs` in the same build.
 #![no_std]
+#![cfg_attr(feature = "const_typeid", feature(const_trait_impl, const_cmp))]
 
-use core::any::TypeId;
-use core::mem::transmute;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+// lets the code generated by the derive macro refer to this crate as `::coe` in the tests.
+#[cfg(all(test, feature = "derive"))]
+extern crate self as coe;
+
+/// Derives [`Coerce`](trait@Coerce) for a struct, coercing it into the same struct with different
+/// type parameters, provided each pair of type parameters is the same type.
+///
+/// Sized structs coerce by value. Structs whose last field is a slice or `str` derive nothing,
+/// since references to them already coerce through the blanket reference impls.
+///
+/// # Example
+/// ```
+/// use coe::Coerce;
+///
+/// #[derive(Coerce)]
+/// struct Meters<T>(T);
+///
+/// fn as_f64<T: 'static>(value: Meters<T>) -> Meters<f64> {
+///     value.coerce()
+/// }
+///
+/// assert_eq!(as_f64(Meters(1.5)).0, 1.5);
+/// ```
+#[cfg(feature = "derive")]
+pub use coe_derive::Coerce;
+
+// lets the code generated by the derive macro follow the `no_inline_always` feature of this crate,
+// rather than the features of the crate it's expanded in.
+#[cfg(all(feature = "derive", not(feature = "no_inline_always")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __coerce_inline {
+    ($($item: tt)*) => {
+        #[inline(always)]
+        $($item)*
+    };
+}
+
+#[cfg(all(feature = "derive", feature = "no_inline_always"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __coerce_inline {
+    ($($item: tt)*) => {
+        #[inline]
+        $($item)*
+    };
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+use alloc::sync::Arc;
+#[cfg(feature = "alloc")]
+use alloc::{borrow::Cow, boxed::Box, rc::Rc, vec::Vec};
+use core::any::{type_name, TypeId};
+use core::cmp::Reverse;
+use core::fmt;
+use core::mem::{transmute, ManuallyDrop, MaybeUninit};
+use core::num::{Saturating, Wrapping};
+use core::ops::{ControlFlow, Range, RangeBounds, RangeFrom, RangeInclusive, RangeTo};
+use core::pin::Pin;
+use core::ptr::NonNull;
 
 /// Returns `true` if `T` and `U` are the same type.
-#[inline(always)]
-pub fn is_same<T: 'static, U: 'static>() -> bool {
+///
+/// Unlike the coercion functions, this is only `#[inline]`, which is enough for the comparison to
+/// fold away, while letting the optimizer merge the comparisons of large dispatch functions.
+#[inline]
+pub fn is_same<T: ?Sized + 'static, U: ?Sized + 'static>() -> bool {
     TypeId::of::<T>() == TypeId::of::<U>()
 }
 
+/// Returns `true` if `A1` and `A2`, `B1` and `B2`, and `C1` and `C2` are all the same type, e.g.
+/// to dispatch on all three operands of a matrix multiplication at once.
+#[inline]
+pub fn is_same3<
+    A1: ?Sized + 'static,
+    A2: ?Sized + 'static,
+    B1: ?Sized + 'static,
+    B2: ?Sized + 'static,
+    C1: ?Sized + 'static,
+    C2: ?Sized + 'static,
+>() -> bool {
+    is_same::<A1, A2>() && is_same::<B1, B2>() && is_same::<C1, C2>()
+}
+
+/// Returns `true` if `id` is the `TypeId` of `T`.
+///
+/// This is meant for checking a `TypeId` obtained at runtime, e.g. from a wire format, against a
+/// generic parameter.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn is_type<T: ?Sized + 'static>(id: TypeId) -> bool {
+    id == TypeId::of::<T>()
+}
+
 /// Checks if `T` and `U` are the same type, and panics if that's not the case.
 #[track_caller]
-#[inline(always)]
-pub fn assert_same<T: 'static, U: 'static>() {
-    assert_eq!(TypeId::of::<T>(), TypeId::of::<U>());
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn assert_same<T: ?Sized + 'static, U: ?Sized + 'static>() {
+    if !is_same::<T, U>() {
+        type_mismatch(None, type_name::<T>(), type_name::<U>());
+    }
+    #[cfg(feature = "log")]
+    log::trace!("coercing {} into {}", type_name::<T>(), type_name::<U>());
+    #[cfg(feature = "specialization_guard")]
+    specialization_guard::record(TypeId::of::<T>(), TypeId::of::<U>());
+}
+
+// kept out of line and non-generic, so that the inlined coercions only contain the comparison on
+// their hot path, and all of them share the same panic code.
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn type_mismatch(msg: Option<&str>, found: &'static str, expected: &'static str) -> ! {
+    #[cfg(feature = "minimal_panic")]
+    {
+        let _ = (msg, found, expected);
+        panic!("type mismatch");
+    }
+    #[cfg(not(feature = "minimal_panic"))]
+    match msg {
+        Some(msg) => panic!("{}: coercion failed: {} is not {}", msg, found, expected),
+        None => panic!("coercion failed: {} is not {}", found, expected),
+    }
+}
+
+/// Returns the name of `T`, as used in the panic messages of failed coercions.
+#[inline]
+pub fn type_name_of<T: ?Sized + 'static>() -> &'static str {
+    type_name::<T>()
+}
+
+/// Returns `true` if `T` and `U` are the same type. Usable in `const` contexts.
+///
+/// Requires the `const_typeid` feature and a nightly compiler, since comparing `TypeId`s in a
+/// `const` context relies on the unstable `const_trait_impl` and `const_cmp` features.
+/// `TypeId::of` itself is usable in `const` contexts since Rust 1.91.
+///
+/// # Example
+/// ```
+/// use coe::is_same_const;
+///
+/// const SAME: bool = is_same_const::<f64, f64>();
+/// const _: () = assert!(SAME);
+/// ```
+#[cfg(feature = "const_typeid")]
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub const fn is_same_const<T: 'static, U: 'static>() -> bool {
+    TypeId::of::<T>() == TypeId::of::<U>()
+}
+
+/// Checks that `T` and `U` are the same type when the calling function is monomorphized, failing
+/// the build instead of panicking at runtime otherwise.
+///
+/// The check is part of every instantiation of the caller, whether or not the call is reached at
+/// runtime, so it should only be used in code that is never instantiated with mismatched types,
+/// e.g. a specialized function that is only called after checking [`is_same`].
+///
+/// Requires the `const_typeid` feature and a nightly compiler.
+///
+/// # Example
+/// ```
+/// use coe::assert_same_or_panic_const;
+///
+/// fn f64_kernel<T: 'static>(slice: &mut [T]) {
+///     assert_same_or_panic_const::<T, f64>();
+/// }
+///
+/// f64_kernel(&mut [1.0f64]);
+/// ```
+///
+/// Instantiating it with mismatched types fails to compile:
+/// ```compile_fail,E0080
+/// use coe::assert_same_or_panic_const;
+///
+/// fn f64_kernel<T: 'static>(slice: &mut [T]) {
+///     assert_same_or_panic_const::<T, f64>();
+/// }
+///
+/// f64_kernel(&mut [1u32]);
+/// ```
+#[cfg(feature = "const_typeid")]
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn assert_same_or_panic_const<T: 'static, U: 'static>() {
+    #[allow(clippy::let_unit_value)]
+    let () = AssertSame::<T, U>::OK;
+}
+
+#[cfg(feature = "const_typeid")]
+struct AssertSame<T, U>(core::marker::PhantomData<(T, U)>);
+
+#[cfg(feature = "const_typeid")]
+impl<T: 'static, U: 'static> AssertSame<T, U> {
+    const OK: () = assert!(is_same_const::<T, U>(), "coercion failed: types differ");
+}
+
+/// Checks that two types are the same type when the surrounding function is monomorphized,
+/// failing the build otherwise. This is the macro form of [`assert_same_or_panic_const`], using
+/// an inline `const` block.
+///
+/// Requires the `const_typeid` feature and a nightly compiler.
+///
+/// # Example
+/// ```
+/// use coe::static_assert_same;
+///
+/// fn f64_kernel<T: 'static>(slice: &mut [T]) {
+///     static_assert_same!(T, f64);
+/// }
+///
+/// f64_kernel(&mut [1.0f64]);
+/// ```
+///
+/// Mismatched types fail to compile:
+/// ```compile_fail,E0080
+/// use coe::static_assert_same;
+///
+/// fn f64_kernel<T: 'static>(slice: &mut [T]) {
+///     static_assert_same!(T, f64);
+/// }
+///
+/// f64_kernel(&mut [1u32]);
+/// ```
+#[cfg(feature = "const_typeid")]
+#[macro_export]
+macro_rules! static_assert_same {
+    ($t: ty, $u: ty $(,)?) => {
+        const {
+            ::core::assert!(
+                $crate::is_same_const::<$t, $u>(),
+                "coercion failed: types differ"
+            )
+        }
+    };
+}
+
+/// Returns `true` if `T` and `U` are the same type, ignoring lifetimes.
+///
+/// This is the same check as [`is_same`] without the `'static` bound, using the technique from
+/// the `typeid` crate: a lifetime-erased trait object is used to obtain the `TypeId` of `T` with
+/// all of its lifetimes replaced by `'static`. Obtaining the `TypeId` this way is sound, since the
+/// trait method never observes a value of type `T` and only returns its `TypeId`.
+///
+/// Because the comparison ignores lifetimes, `&'a u32` and `&'static u32` are considered the same
+/// type. A `true` result therefore does *not* prove that one type can be transmuted into the
+/// other, and must not be used on its own to justify an unsafe coercion between types that may
+/// differ in their lifetimes.
+#[cfg(feature = "nonstatic")]
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn is_same_nonstatic<T: ?Sized, U: ?Sized>() -> bool {
+    nonstatic_type_id::<T>() == nonstatic_type_id::<U>()
+}
+
+#[cfg(feature = "nonstatic")]
+trait NonStaticAny {
+    fn type_id(&self) -> TypeId
+    where
+        Self: 'static;
+}
+
+#[cfg(feature = "nonstatic")]
+impl<T: ?Sized> NonStaticAny for core::marker::PhantomData<T> {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    fn type_id(&self) -> TypeId
+    where
+        Self: 'static,
+    {
+        TypeId::of::<T>()
+    }
+}
+
+#[cfg(feature = "nonstatic")]
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+fn nonstatic_type_id<T: ?Sized>() -> TypeId {
+    let phantom = core::marker::PhantomData::<T>;
+    let phantom: &dyn NonStaticAny = &phantom;
+    // SAFETY: `NonStaticAny::type_id` doesn't access any data with the erased lifetimes.
+    let phantom: &(dyn NonStaticAny + 'static) = unsafe { transmute(phantom) };
+    phantom.type_id()
+}
+
+/// Returns `true` if the referents of `_a` and `_b` have the same type.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn is_same_val<T: 'static, U: 'static>(_a: &T, _b: &U) -> bool {
+    is_same::<T, U>()
+}
+
+/// Checks if the referents of `_a` and `_b` have the same type, and panics if that's not the case.
+#[track_caller]
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn assert_same_val<T: 'static, U: 'static>(_a: &T, _b: &U) {
+    assert_same::<T, U>();
+}
+
+/// Checks if `T` and `U` are the same type, and panics with the given context message if that's
+/// not the case.
+#[track_caller]
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn assert_same_msg<T: ?Sized + 'static, U: ?Sized + 'static>(msg: &str) {
+    if !is_same::<T, U>() {
+        type_mismatch(Some(msg), type_name::<T>(), type_name::<U>());
+    }
+}
+
+/// Checks if `T` and `U` are the same type, and returns an error if that's not the case.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn check_same<T: ?Sized + 'static, U: ?Sized + 'static>() -> Result<(), TypeMismatch> {
+    if is_same::<T, U>() {
+        Ok(())
+    } else {
+        Err(TypeMismatch::new::<T, U>())
+    }
+}
+
+/// Error describing a failed coercion from one type to another.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TypeMismatch {
+    expected: &'static str,
+    found: &'static str,
+}
+
+impl TypeMismatch {
+    /// Returns the error for a failed coercion from `T` to `U`.
+    #[inline]
+    pub fn new<T: ?Sized, U: ?Sized>() -> Self {
+        Self {
+            expected: type_name::<U>(),
+            found: type_name::<T>(),
+        }
+    }
+
+    /// Returns the name of the target type.
+    #[inline]
+    pub fn expected(&self) -> &'static str {
+        self.expected
+    }
+
+    /// Returns the name of the source type.
+    #[inline]
+    pub fn found(&self) -> &'static str {
+        self.found
+    }
+}
+
+impl fmt::Display for TypeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected type `{}`, found type `{}`",
+            self.expected, self.found
+        )
+    }
 }
 
 /// Trait for performing coercion from one type to another, where the types
 /// are identical but the compiler can't prove it.
 ///
+/// The reference impls apply to any `'static` pointee, sized or not, preserving the metadata of
+/// fat pointers. So `&[(T, T)]` coerces into `&[(U, U)]`, `&[[T; N]]` into `&[[U; N]]`,
+/// `&[Option<T>]` into `&[Option<U>]`, and a reference to a custom unsized wrapper into a
+/// reference to the same wrapper over `U` whenever `T` and `U` are the same type.
+/// Likewise, the array impl coerces nested arrays such as `[[T; M]; N]` into `[[U; M]; N]`, both
+/// by value and by reference.
+///
+/// Slice coercions reinterpret the whole fat pointer, so the length is preserved as is. The length
+/// counts elements, not bytes, which also holds for zero-sized element types.
+///
 /// # Example
 /// ```
 /// use coe::{Coerce, is_same};
@@ -48,84 +439,5147 @@ pub trait Coerce<U> {
     fn coerce(self) -> U;
 }
 
-impl<'a, T: 'static, U: 'static> Coerce<&'a U> for &'a T {
-    #[inline(always)]
+/// The lifetime of the reference is preserved, so a `&'static T` coerces into a `&'static U`,
+/// e.g. for lookup tables stored in `static`s.
+///
+/// Since the referent may be unsized, this also covers slices behind interior mutability wrappers,
+/// e.g. `&Cell<[T]>` into `&Cell<[U]>`, or `&UnsafeCell<[T]>` and user-defined `Sync` wrappers
+/// around it, as used to share a buffer across the threads of a parallel kernel. The coerced
+/// reference has the same type as the original one, so it's exactly as `Send` and `Sync`.
+impl<'a, T: ?Sized + 'static, U: ?Sized + 'static> Coerce<&'a U> for &'a T {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
     #[track_caller]
     fn coerce(self) -> &'a U {
         assert_same::<T, U>();
-        unsafe { transmute(self) }
+        unsafe { core::mem::transmute_copy::<&T, &U>(&self) }
     }
 }
 
-impl<'a, T: 'static, U: 'static> Coerce<&'a mut U> for &'a mut T {
-    #[inline(always)]
+impl<'a, T: ?Sized + 'static, U: ?Sized + 'static> Coerce<&'a mut U> for &'a mut T {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
     #[track_caller]
     fn coerce(self) -> &'a mut U {
         assert_same::<T, U>();
-        unsafe { transmute(self) }
+        unsafe { core::mem::transmute_copy::<&mut T, &mut U>(&ManuallyDrop::new(self)) }
     }
 }
 
-impl<'a, T: 'static, U: 'static> Coerce<&'a [U]> for &'a [T] {
-    #[inline(always)]
+// the mutable borrow is moved into `coerce`, so it stays exclusive for as long as the shared
+// slice is alive. Reborrow with `&*slice` first to get a genuinely shared borrow.
+impl<'a, T: 'static, U: 'static> Coerce<&'a [U]> for &'a mut [T] {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
     #[track_caller]
     fn coerce(self) -> &'a [U] {
+        (&*self).coerce()
+    }
+}
+
+impl<T: 'static, U: 'static, const N: usize> Coerce<[U; N]> for [T; N] {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce(self) -> [U; N] {
         assert_same::<T, U>();
-        unsafe { transmute(self) }
+        unsafe { core::mem::transmute_copy(&core::mem::ManuallyDrop::new(self)) }
     }
 }
 
-impl<'a, T: 'static, U: 'static> Coerce<&'a mut [U]> for &'a mut [T] {
-    #[inline(always)]
+#[cfg(feature = "alloc")]
+impl<T: 'static, U: 'static> Coerce<Box<U>> for Box<T> {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
     #[track_caller]
-    fn coerce(self) -> &'a mut [U] {
+    fn coerce(self) -> Box<U> {
         assert_same::<T, U>();
-        unsafe { transmute(self) }
+        unsafe { Box::from_raw(Box::into_raw(self) as *mut U) }
     }
 }
 
-/// Free function that defers to the `Coerce` trait implementation.
-#[inline(always)]
-pub fn coerce<T: Coerce<U>, U>(value: T) -> U {
-    value.coerce()
+#[cfg(feature = "alloc")]
+impl<T: 'static, U: 'static> Coerce<Box<[U]>> for Box<[T]> {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce(self) -> Box<[U]> {
+        assert_same::<T, U>();
+        unsafe { Box::from_raw(Box::into_raw(self) as *mut [U]) }
+    }
 }
 
-/// Similar to [`coerce`] but operates on any lifetime-free type.
-#[inline(always)]
-pub fn coerce_static<T: 'static, U: 'static>(value: T) -> U {
-    assert_same::<T, U>();
-    unsafe { core::mem::transmute_copy(&core::mem::ManuallyDrop::new(value)) }
+#[cfg(feature = "alloc")]
+impl<T: 'static, U: 'static> Coerce<Vec<U>> for Vec<T> {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce(self) -> Vec<U> {
+        assert_same::<T, U>();
+        let mut vec = core::mem::ManuallyDrop::new(self);
+        unsafe { Vec::from_raw_parts(vec.as_mut_ptr() as *mut U, vec.len(), vec.capacity()) }
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl<T: ?Sized + 'static, U: ?Sized + 'static> Coerce<*const U> for *const T {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce(self) -> *const U {
+        assert_same::<T, U>();
+        unsafe { core::mem::transmute_copy::<*const T, *const U>(&self) }
+    }
+}
 
-    #[test]
-    fn test_coerce() {
-        let mut ints = [0, 1, 2u32];
-        let mut floats = [0.0, 1.0, 2.0f64];
+impl<T: ?Sized + 'static, U: ?Sized + 'static> Coerce<*mut U> for *mut T {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce(self) -> *mut U {
+        assert_same::<T, U>();
+        unsafe { core::mem::transmute_copy::<*mut T, *mut U>(&self) }
+    }
+}
 
-        pub fn generic_fn<T: 'static>(factor: T, slice: &mut [T]) {
-            if is_same::<u32, T>() {
-                let slice: &mut [u32] = slice.coerce();
-                let factor: u32 = coerce_static(factor);
-                for x in slice {
-                    *x = 2 * factor * *x;
-                }
-            } else if is_same::<f64, T>() {
-                let slice: &mut [f64] = slice.coerce();
-                let factor: f64 = coerce_static(factor);
-                for x in slice {
-                    *x = factor * *x;
-                }
+impl<T: 'static, U: 'static> Coerce<Option<U>> for Option<T> {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce(self) -> Option<U> {
+        assert_same::<T, U>();
+        unsafe { core::mem::transmute_copy(&core::mem::ManuallyDrop::new(self)) }
+    }
+}
+
+impl<T: 'static, U: 'static, E: 'static, F: 'static> Coerce<Result<U, F>> for Result<T, E> {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce(self) -> Result<U, F> {
+        assert_same::<T, U>();
+        assert_same::<E, F>();
+        unsafe { core::mem::transmute_copy(&core::mem::ManuallyDrop::new(self)) }
+    }
+}
+
+impl<B: 'static, C: 'static, D: 'static, E: 'static> Coerce<ControlFlow<D, E>>
+    for ControlFlow<B, C>
+{
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce(self) -> ControlFlow<D, E> {
+        assert_same::<B, D>();
+        assert_same::<C, E>();
+        unsafe { core::mem::transmute_copy(&core::mem::ManuallyDrop::new(self)) }
+    }
+}
+
+macro_rules! impl_coerce_tuple {
+    ($(($T: ident, $U: ident)),*) => {
+        impl<$($T: 'static, $U: 'static),*> Coerce<($($U,)*)> for ($($T,)*) {
+            #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+            #[cfg_attr(feature = "no_inline_always", inline)]
+            #[track_caller]
+            fn coerce(self) -> ($($U,)*) {
+                $(assert_same::<$T, $U>();)*
+                unsafe { core::mem::transmute_copy(&core::mem::ManuallyDrop::new(self)) }
             }
         }
+    };
+}
 
-        generic_fn(2, &mut ints);
-        generic_fn(2.0, &mut floats);
+impl_coerce_tuple!((T0, U0), (T1, U1));
+impl_coerce_tuple!((T0, U0), (T1, U1), (T2, U2));
+impl_coerce_tuple!((T0, U0), (T1, U1), (T2, U2), (T3, U3));
 
-        assert_eq!(ints, [0, 4, 8]);
-        assert_eq!(floats, [0.0, 2.0, 4.0]);
+#[cfg(feature = "alloc")]
+impl<T: ?Sized + 'static, U: ?Sized + 'static> Coerce<Rc<U>> for Rc<T> {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce(self) -> Rc<U> {
+        assert_same::<T, U>();
+        unsafe {
+            Rc::from_raw(core::mem::transmute_copy::<*const T, *const U>(
+                &Rc::into_raw(self),
+            ))
+        }
+    }
+}
+
+#[cfg(all(feature = "alloc", target_has_atomic = "ptr"))]
+impl<T: ?Sized + 'static, U: ?Sized + 'static> Coerce<Arc<U>> for Arc<T> {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce(self) -> Arc<U> {
+        assert_same::<T, U>();
+        unsafe {
+            Arc::from_raw(core::mem::transmute_copy::<*const T, *const U>(
+                &Arc::into_raw(self),
+            ))
+        }
+    }
+}
+
+impl<T: 'static, U: 'static> Coerce<core::marker::PhantomData<U>> for core::marker::PhantomData<T> {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce(self) -> core::marker::PhantomData<U> {
+        assert_same::<T, U>();
+        core::marker::PhantomData
+    }
+}
+
+impl<T: 'static, U: 'static> Coerce<core::cell::Cell<U>> for core::cell::Cell<T> {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce(self) -> core::cell::Cell<U> {
+        assert_same::<T, U>();
+        unsafe { core::mem::transmute_copy(&core::mem::ManuallyDrop::new(self)) }
+    }
+}
+
+macro_rules! impl_coerce_by_value {
+    ($($ty: ident),* $(,)?) => {$(
+        impl<T: 'static, U: 'static> Coerce<$ty<U>> for $ty<T> {
+            #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+            #[cfg_attr(feature = "no_inline_always", inline)]
+            #[track_caller]
+            fn coerce(self) -> $ty<U> {
+                assert_same::<T, U>();
+                unsafe { core::mem::transmute_copy(&core::mem::ManuallyDrop::new(self)) }
+            }
+        }
+    )*};
+}
+
+impl_coerce_by_value!(Range, RangeInclusive, RangeFrom, RangeTo);
+impl_coerce_by_value!(Wrapping, Saturating, Reverse);
+impl_coerce_by_value!(MaybeUninit, ManuallyDrop);
+
+impl<T: ?Sized + 'static, U: ?Sized + 'static> Coerce<NonNull<U>> for NonNull<T> {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce(self) -> NonNull<U> {
+        assert_same::<T, U>();
+        unsafe { core::mem::transmute_copy::<NonNull<T>, NonNull<U>>(&self) }
+    }
+}
+
+// `NonZero<T>` can't be named generically on stable, since its bound is unstable. The concrete
+// types only coerce into themselves.
+macro_rules! impl_coerce_identity {
+    ($($ty: ty),* $(,)?) => {$(
+        impl Coerce<$ty> for $ty {
+            #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+            #[cfg_attr(feature = "no_inline_always", inline)]
+            fn coerce(self) -> $ty {
+                self
+            }
+        }
+    )*};
+}
+
+impl_coerce_identity!(
+    core::num::NonZeroU8,
+    core::num::NonZeroU16,
+    core::num::NonZeroU32,
+    core::num::NonZeroU64,
+    core::num::NonZeroU128,
+    core::num::NonZeroUsize,
+    core::num::NonZeroI8,
+    core::num::NonZeroI16,
+    core::num::NonZeroI32,
+    core::num::NonZeroI64,
+    core::num::NonZeroI128,
+    core::num::NonZeroIsize,
+);
+
+impl<T: 'static, R: 'static, U: 'static, S: 'static> Coerce<fn(U) -> S> for fn(T) -> R {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce(self) -> fn(U) -> S {
+        assert_same::<T, U>();
+        assert_same::<R, S>();
+        unsafe { transmute::<fn(T) -> R, fn(U) -> S>(self) }
+    }
+}
+
+impl<'a, T: 'static, U: 'static> Coerce<Pin<&'a U>> for Pin<&'a T> {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce(self) -> Pin<&'a U> {
+        assert_same::<T, U>();
+        unsafe { transmute(self) }
+    }
+}
+
+impl<'a, T: 'static, U: 'static> Coerce<Pin<&'a mut U>> for Pin<&'a mut T> {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce(self) -> Pin<&'a mut U> {
+        assert_same::<T, U>();
+        unsafe { transmute(self) }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: 'static, U: 'static> Coerce<Pin<Box<U>>> for Pin<Box<T>> {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce(self) -> Pin<Box<U>> {
+        assert_same::<T, U>();
+        // SAFETY: the pointee stays in the same allocation, so it is never moved.
+        unsafe { Pin::new_unchecked(Pin::into_inner_unchecked(self).coerce()) }
+    }
+}
+
+#[cfg(target_has_atomic = "ptr")]
+impl<T: 'static, U: 'static> Coerce<core::sync::atomic::AtomicPtr<U>>
+    for core::sync::atomic::AtomicPtr<T>
+{
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce(self) -> core::sync::atomic::AtomicPtr<U> {
+        assert_same::<T, U>();
+        unsafe { transmute(self) }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T: 'static + Clone, U: 'static + Clone> Coerce<Cow<'a, U>> for Cow<'a, T> {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce(self) -> Cow<'a, U> {
+        assert_same::<T, U>();
+        unsafe { core::mem::transmute_copy(&core::mem::ManuallyDrop::new(self)) }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T: 'static + Clone, U: 'static + Clone> Coerce<Cow<'a, [U]>> for Cow<'a, [T]> {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce(self) -> Cow<'a, [U]> {
+        assert_same::<T, U>();
+        unsafe { core::mem::transmute_copy(&core::mem::ManuallyDrop::new(self)) }
+    }
+}
+
+/// Trait for performing fallible coercion from one type to another, returning the original value
+/// if the types are not identical.
+///
+/// # Example
+/// ```
+/// use coe::TryCoerce;
+///
+/// fn double<T: 'static>(slice: &mut [T]) {
+///     let slice = match slice.try_coerce() {
+///         Ok(slice) => {
+///             let slice: &mut [f64] = slice;
+///             return slice.iter_mut().for_each(|x| *x *= 2.0);
+///         }
+///         Err(slice) => slice,
+///     };
+///     match slice.try_coerce() {
+///         Ok(slice) => {
+///             let slice: &mut [u32] = slice;
+///             slice.iter_mut().for_each(|x| *x *= 2);
+///         }
+///         Err(_) => panic!("unsupported type"),
+///     }
+/// }
+///
+/// let mut floats = [1.0, 2.0f64];
+/// let mut ints = [1, 2u32];
+/// double(&mut floats);
+/// double(&mut ints);
+/// assert_eq!(floats, [2.0, 4.0]);
+/// assert_eq!(ints, [2, 4]);
+/// ```
+pub trait TryCoerce<U>: Sized {
+    fn try_coerce(self) -> Result<U, Self>;
+}
+
+impl<'a, T: ?Sized + 'static, U: ?Sized + 'static> TryCoerce<&'a U> for &'a T {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    fn try_coerce(self) -> Result<&'a U, Self> {
+        if is_same::<T, U>() {
+            Ok(unsafe { core::mem::transmute_copy::<&T, &U>(&self) })
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl<'a, T: ?Sized + 'static, U: ?Sized + 'static> TryCoerce<&'a mut U> for &'a mut T {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    fn try_coerce(self) -> Result<&'a mut U, Self> {
+        if is_same::<T, U>() {
+            Ok(unsafe { core::mem::transmute_copy::<&mut T, &mut U>(&ManuallyDrop::new(self)) })
+        } else {
+            Err(self)
+        }
+    }
+}
+
+/// Free function that defers to the `Coerce` trait implementation.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn coerce<T: Coerce<U>, U>(value: T) -> U {
+    value.coerce()
+}
+
+/// Coerces two values of type `T` into values of type `U`, checking that the types match only
+/// once.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce2<T: 'static, U: 'static>(a: T, b: T) -> (U, U) {
+    assert_same::<T, U>();
+    unsafe { (coerce_unchecked(a), coerce_unchecked(b)) }
+}
+
+/// Coerces three mutable slices of `T` into mutable slices of `U`, checking that the types match
+/// only once, e.g. for the input, output and scratch buffers of a kernel.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_each3<'a, T: 'static, U: 'static>(
+    a: &'a mut [T],
+    b: &'a mut [T],
+    c: &'a mut [T],
+) -> (&'a mut [U], &'a mut [U], &'a mut [U]) {
+    assert_same::<T, U>();
+    unsafe {
+        (
+            coerce_unchecked(a),
+            coerce_unchecked(b),
+            coerce_unchecked(c),
+        )
+    }
+}
+
+/// Coerces `a`, `b` and `c` into `A2`, `B2` and `C2` respectively, checking that each pair of
+/// types matches with a single assertion.
+///
+/// # Panics
+/// Panics if any of the pairs doesn't match, naming both tuples of types.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce3<A1: 'static, A2: 'static, B1: 'static, B2: 'static, C1: 'static, C2: 'static>(
+    a: A1,
+    b: B1,
+    c: C1,
+) -> (A2, B2, C2) {
+    assert_same::<(A1, B1, C1), (A2, B2, C2)>();
+    unsafe {
+        (
+            coerce_unchecked(a),
+            coerce_unchecked(b),
+            coerce_unchecked(c),
+        )
+    }
+}
+
+/// Coerces a reference to `T` into a reference to `U`.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_ref<T: ?Sized + 'static, U: ?Sized + 'static>(value: &T) -> &U {
+    value.coerce()
+}
+
+/// Coerces a mutable reference to `T` into a mutable reference to `U`.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_mut<T: ?Sized + 'static, U: ?Sized + 'static>(value: &mut T) -> &mut U {
+    value.coerce()
+}
+
+/// Reinterprets the storage behind a mutable reference to `T` as storage of type `U`, so that
+/// in-place algorithms can keep writing to it through the concrete type.
+///
+/// This is the same as [`coerce_mut`], named for pairing with in-place algorithms.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_in_place<T: 'static, U: 'static>(slot: &mut T) -> &mut U {
+    slot.coerce()
+}
+
+/// Coerces a reference to `T` into a reference to `U` if `T` and `U` are the same type, or returns
+/// `fallback` otherwise.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn coerce_ref_or<'a, T: ?Sized + 'static, U: ?Sized + 'static>(
+    value: &'a T,
+    fallback: &'a U,
+) -> &'a U {
+    value.try_coerce().unwrap_or(fallback)
+}
+
+/// Coerces a reference to `T` into a reference to `U` with a shorter lifetime.
+///
+/// # Example
+/// ```
+/// use coe::coerce_ref_shorten;
+///
+/// static VALUE: f64 = 1.5;
+///
+/// fn get<'b, T: 'static>(value: &'static T, _scope: &'b ()) -> &'b f64 {
+///     coerce_ref_shorten(value)
+/// }
+///
+/// assert_eq!(*get(&VALUE, &()), 1.5);
+/// ```
+///
+/// The lifetime can't be extended:
+/// ```compile_fail
+/// use coe::coerce_ref_shorten;
+///
+/// fn extend<'a, T: 'static>(value: &'a T) -> &'static f64 {
+///     coerce_ref_shorten(value)
+/// }
+/// ```
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_ref_shorten<'a: 'b, 'b, T: ?Sized + 'static, U: ?Sized + 'static>(
+    value: &'a T,
+) -> &'b U {
+    value.coerce()
+}
+
+/// Coerces the data pointer of a slice passed as a `(ptr, len)` pair, as is common at FFI
+/// boundaries, from `T` to `U`. The length is unchanged and left to the caller.
+///
+/// This only casts the pointer and is therefore safe. Rebuilding a slice from the parts with
+/// `core::slice::from_raw_parts` has the usual requirements on `ptr` and `len`.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_slice_parts<T: 'static, U: 'static>(ptr: *const T, len: usize) -> (*const U, usize) {
+    (ptr.coerce(), len)
+}
+
+/// Mutable version of [`coerce_slice_parts`].
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_slice_parts_mut<T: 'static, U: 'static>(ptr: *mut T, len: usize) -> (*mut U, usize) {
+    (ptr.coerce(), len)
+}
+
+/// Reinterprets `bytes` as a slice of `U` if `tag` is the `TypeId` of `U`, or returns `None`
+/// otherwise, e.g. for values stored in a byte buffer along with a runtime type tag.
+///
+/// # Safety
+/// If `tag` matches, `bytes` must be aligned for `U`, its length must be a multiple of the size of
+/// `U`, and it must contain valid values of `U`. The first two requirements are checked in debug
+/// builds.
+///
+/// Using this function with a zero-sized `U` fails to compile:
+/// ```compile_fail,E0080
+/// let _ = unsafe { coe::coerce_dyn_slice::<()>(&[], core::any::TypeId::of::<()>()) };
+/// ```
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+// `is_multiple_of` and `<*const U>::is_aligned` would needlessly raise the minimum Rust version.
+#[allow(clippy::manual_is_multiple_of)]
+pub unsafe fn coerce_dyn_slice<U: 'static>(bytes: &[u8], tag: TypeId) -> Option<&[U]> {
+    const { assert!(core::mem::size_of::<U>() != 0) };
+    if !is_type::<U>(tag) {
+        return None;
+    }
+    let size = core::mem::size_of::<U>();
+    debug_assert!(bytes.as_ptr() as usize % core::mem::align_of::<U>() == 0);
+    debug_assert!(bytes.len() % size == 0);
+    Some(core::slice::from_raw_parts(
+        bytes.as_ptr().cast::<U>(),
+        bytes.len() / size,
+    ))
+}
+
+/// Coerces a slice of `T` into a slice of `U`.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_slice<T: 'static, U: 'static>(slice: &[T]) -> &[U] {
+    slice.coerce()
+}
+
+/// Coerces a mutable slice of `T` into a mutable slice of `U`.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_slice_mut<T: 'static, U: 'static>(slice: &mut [T]) -> &mut [U] {
+    slice.coerce()
+}
+
+/// Narrows a slice of `T` to `range` and coerces it into a slice of `U`.
+///
+/// # Panics
+/// Panics if `T` and `U` are not the same type, or if `range` is out of bounds, like indexing the
+/// slice with it.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_range<T: 'static, U: 'static, R: RangeBounds<usize>>(slice: &[T], range: R) -> &[U] {
+    slice[(range.start_bound().cloned(), range.end_bound().cloned())].coerce()
+}
+
+/// Mutable version of [`coerce_range`].
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_range_mut<T: 'static, U: 'static, R: RangeBounds<usize>>(
+    slice: &mut [T],
+    range: R,
+) -> &mut [U] {
+    (&mut slice[(range.start_bound().cloned(), range.end_bound().cloned())]).coerce()
+}
+
+/// Coerces a mutable slice iterator over `T` into one over `U`, keeping its position, e.g. to
+/// switch to a specialized element type in the middle of a pipeline.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_iter_mut<'a, T: 'static, U: 'static>(
+    iter: core::slice::IterMut<'a, T>,
+) -> core::slice::IterMut<'a, U> {
+    coerce_slice_mut(iter.into_slice()).iter_mut()
+}
+
+/// Dereferences `c` to a slice of `T` and coerces it into a slice of `U`.
+///
+/// This works for any container that dereferences to a slice, such as `Vec<T>` or `Box<[T]>`.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_deref_slice<C: core::ops::Deref<Target = [T]>, T: 'static, U: 'static>(
+    c: &C,
+) -> &[U] {
+    coerce_slice(c)
+}
+
+/// Returns `slice` as a byte slice if `T` is `u8`, or `None` otherwise.
+///
+/// This is meant for reusing a byte-oriented fast path from generic code.
+///
+/// # Example
+/// ```
+/// use coe::as_bytes_if;
+///
+/// fn count_zeros<T: 'static + Default + PartialEq>(slice: &[T]) -> usize {
+///     match as_bytes_if(slice) {
+///         Some(bytes) => bytes.iter().filter(|&&b| b == 0).count(),
+///         None => slice.iter().filter(|x| **x == T::default()).count(),
+///     }
+/// }
+///
+/// assert_eq!(count_zeros(&[0u8, 1, 0]), 2);
+/// assert_eq!(count_zeros(&[0u32, 1, 0]), 2);
+/// ```
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn as_bytes_if<T: 'static>(slice: &[T]) -> Option<&[u8]> {
+    if is_same::<T, u8>() {
+        Some(slice.coerce())
+    } else {
+        None
+    }
+}
+
+/// Coerces a slice of `T` into a slice of `U` if `T` and `U` are the same type and `pred` holds
+/// for the length of the slice, or returns `None` otherwise.
+///
+/// # Example
+/// ```
+/// use coe::coerce_slice_if;
+///
+/// fn simd_view<T: 'static>(slice: &[T]) -> Option<&[f64]> {
+///     coerce_slice_if(slice, |len| len % 4 == 0)
+/// }
+///
+/// assert!(simd_view(&[0.0f64; 8]).is_some());
+/// assert!(simd_view(&[0.0f64; 7]).is_none());
+/// assert!(simd_view(&[0u64; 8]).is_none());
+/// ```
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn coerce_slice_if<T: 'static, U: 'static>(
+    slice: &[T],
+    pred: impl FnOnce(usize) -> bool,
+) -> Option<&[U]> {
+    if is_same::<T, U>() && pred(slice.len()) {
+        Some(slice.coerce())
+    } else {
+        None
+    }
+}
+
+/// Coerces an array of `T` into an array of `U`.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_array<T: 'static, U: 'static, const N: usize>(arr: [T; N]) -> [U; N] {
+    assert_same::<T, U>();
+    unsafe { core::mem::transmute_copy(&core::mem::ManuallyDrop::new(arr)) }
+}
+
+/// Coerces a reference to an array of `T` into a slice of `U`.
+///
+/// There is no `Coerce` impl from `&[T; N]` to `&[U]`, since it would overlap with the blanket
+/// reference impl, so this function is the way to unsize and coerce in one step.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_array_as_slice<T: 'static, U: 'static, const N: usize>(arr: &[T; N]) -> &[U] {
+    coerce_slice(arr)
+}
+
+/// Coerces a mutable reference to an array of `T` into a mutable slice of `U`.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_array_as_slice_mut<T: 'static, U: 'static, const N: usize>(
+    arr: &mut [T; N],
+) -> &mut [U] {
+    coerce_slice_mut(arr)
+}
+
+/// Coerces a reference to an array of `T` into a reference to an array of `U` of the same length.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_ref_array<T: 'static, U: 'static, const N: usize>(arr: &[T; N]) -> &[U; N] {
+    arr.coerce()
+}
+
+/// Coerces a mutable reference to an array of `T` into a mutable reference to an array of `U` of
+/// the same length.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_mut_array<T: 'static, U: 'static, const N: usize>(arr: &mut [T; N]) -> &mut [U; N] {
+    arr.coerce()
+}
+
+/// Coerces a reference to an array of `N` elements of `T` into a reference to an array of `K`
+/// blocks of `M` elements of `U`.
+///
+/// # Example
+/// ```
+/// use coe::coerce_as_blocks;
+///
+/// let blocks: &[[f64; 2]; 3] = coerce_as_blocks(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0f64]);
+/// assert_eq!(blocks[2], [5.0, 6.0]);
+/// ```
+///
+/// The dimensions are checked at compile time:
+/// ```compile_fail,E0080
+/// use coe::coerce_as_blocks;
+///
+/// let blocks: &[[f64; 4]; 2] = coerce_as_blocks(&[0.0f64; 6]);
+/// ```
+///
+/// # Panics
+/// Panics if `T` and `U` are not the same type.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_as_blocks<T: 'static, U: 'static, const N: usize, const M: usize, const K: usize>(
+    arr: &[T; N],
+) -> &[[U; M]; K] {
+    const { assert!(N == M * K) };
+    let arr: &[U; N] = arr.coerce();
+    unsafe { &*(arr as *const [U; N] as *const [[U; M]; K]) }
+}
+
+/// Coerces a slice of `T` into a slice of `[U; N]` arrays, followed by the remaining elements
+/// that don't fit into a whole array.
+///
+/// # Panics
+/// Panics if `T` and `U` are not the same type, or if `N` is zero.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_array_chunks<T: 'static, U: 'static, const N: usize>(
+    slice: &[T],
+) -> (&[[U; N]], &[U]) {
+    let slice: &[U] = slice.coerce();
+    assert!(N != 0);
+    let len = slice.len() / N;
+    let (head, tail) = slice.split_at(len * N);
+    (
+        unsafe { core::slice::from_raw_parts(head.as_ptr() as *const [U; N], len) },
+        tail,
+    )
+}
+
+/// Coerces a slice of `T` into a slice of `[U; N]` arrays if its length is a multiple of `N`, or
+/// returns `None` otherwise.
+///
+/// # Panics
+/// Panics if `T` and `U` are not the same type, or if `N` is zero.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_exact_chunks<T: 'static, U: 'static, const N: usize>(
+    slice: &[T],
+) -> Option<&[[U; N]]> {
+    match coerce_array_chunks(slice) {
+        (head, []) => Some(head),
+        _ => None,
+    }
+}
+
+/// Coerces a slice of `T` into an iterator over the whole `[U; N]` chunks at its start, skipping
+/// the remaining elements that don't fit into a whole chunk.
+///
+/// # Panics
+/// Panics if `T` and `U` are not the same type, or if `N` is zero.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_chunks_iter<T: 'static, U: 'static, const N: usize>(
+    slice: &[T],
+) -> core::slice::Iter<'_, [U; N]> {
+    coerce_array_chunks(slice).0.iter()
+}
+
+/// Coerces a slice of `T` into `W`-wide lanes of `U` followed by the scalar tail that doesn't fill
+/// a whole lane if `T` and `U` are the same type, or returns `None` otherwise.
+///
+/// # Panics
+/// Panics if `W` is zero.
+///
+/// # Example
+/// ```
+/// fn sum<T: 'static + Copy + Into<f64>>(values: &[T]) -> f64 {
+///     match coe::coerce_lanes::<T, f64, 4>(values) {
+///         Some((lanes, tail)) => {
+///             let mut acc = [0.0; 4];
+///             for lane in lanes {
+///                 for (acc, x) in acc.iter_mut().zip(lane) {
+///                     *acc += x;
+///                 }
+///             }
+///             acc.iter().sum::<f64>() + tail.iter().sum::<f64>()
+///         }
+///         None => values.iter().map(|&x| x.into()).sum(),
+///     }
+/// }
+///
+/// assert_eq!(sum(&[1.0f64; 7]), 7.0);
+/// assert_eq!(sum(&[1.0f32; 7]), 7.0);
+/// ```
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_lanes<T: 'static, U: 'static, const W: usize>(
+    slice: &[T],
+) -> Option<(&[[U; W]], &[U])> {
+    if is_same::<T, U>() {
+        Some(coerce_array_chunks(slice))
+    } else {
+        None
+    }
+}
+
+/// Coerces a slice of `T` into a slice of `U`, then splits it into a prefix, an aligned middle
+/// reinterpreted as a slice of `V`, and a suffix, like `<[U]>::align_to`.
+///
+/// # Safety
+/// Same as `<[U]>::align_to`: any sequence of `U` values filling a `V` must be a valid `V`, as is
+/// the case when `V` is a SIMD vector of `U`.
+///
+/// # Panics
+/// Panics if `T` and `U` are not the same type.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub unsafe fn coerce_align_to<T: 'static, U: 'static, V>(slice: &[T]) -> (&[U], &[V], &[U]) {
+    coerce_slice::<T, U>(slice).align_to()
+}
+
+/// Splits a mutable slice of `T` at `mid` and coerces both halves into mutable slices of `U`.
+///
+/// # Panics
+/// Panics if `T` and `U` are not the same type, or if `mid > slice.len()`.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_split_at_mut<T: 'static, U: 'static>(
+    slice: &mut [T],
+    mid: usize,
+) -> (&mut [U], &mut [U]) {
+    let slice: &mut [U] = slice.coerce();
+    slice.split_at_mut(mid)
+}
+
+/// Moves the elements of `src` into `dst`, coercing them from `T` into `U`.
+///
+/// # Safety
+/// The elements of `src` are moved out bitwise, so they must be treated as uninitialized after the
+/// call: they must not be read or dropped again, e.g. because the storage of `src` is wrapped in
+/// [`ManuallyDrop`] or is a `Vec` whose length is set to zero afterwards.
+///
+/// # Panics
+/// Panics if `T` and `U` are not the same type, or if `src` and `dst` have different lengths.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub unsafe fn coerce_move_into<T: 'static, U: 'static>(src: &mut [T], dst: &mut [MaybeUninit<U>]) {
+    let src: &mut [U] = src.coerce();
+    assert_eq!(src.len(), dst.len());
+    core::ptr::copy_nonoverlapping(src.as_ptr(), dst.as_mut_ptr() as *mut U, src.len());
+}
+
+/// Coerces a slice of references to `T` into a slice of references to `U`.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_slice_of_refs<'a, 'b, T: 'static, U: 'static>(slice: &'a [&'b T]) -> &'a [&'b U] {
+    assert_same::<T, U>();
+    unsafe { &*(slice as *const [&'b T] as *const [&'b U]) }
+}
+
+/// Coerces a reference to a reference to `T` into a reference to a reference to `U`.
+///
+/// When the inner lifetime is `'static`, the `Coerce` impl for references covers this case, and
+/// a separate impl for arbitrary inner lifetimes would overlap with it.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_ref_ref<'a, 'b, T: 'static, U: 'static>(value: &'a &'b T) -> &'a &'b U {
+    assert_same::<T, U>();
+    unsafe { &*(value as *const &'b T as *const &'b U) }
+}
+
+/// Coerces a mutable reference to a mutable reference to `T` into a mutable reference to a
+/// mutable reference to `U`.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_mut_mut<'a, 'b, T: 'static, U: 'static>(
+    value: &'a mut &'b mut T,
+) -> &'a mut &'b mut U {
+    assert_same::<T, U>();
+    unsafe { &mut *(value as *mut &'b mut T as *mut &'b mut U) }
+}
+
+/// Coerces a slice of slices of `T` into a slice of slices of `U`.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_slice_of_slices<'a, 'b, T: 'static, U: 'static>(
+    slice: &'a [&'b [T]],
+) -> &'a [&'b [U]] {
+    assert_same::<T, U>();
+    unsafe { &*(slice as *const [&'b [T]] as *const [&'b [U]]) }
+}
+
+/// Coerces a mutable slice of mutable slices of `T` into a mutable slice of mutable slices of
+/// `U`.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_slice_of_slices_mut<'a, 'b, T: 'static, U: 'static>(
+    slice: &'a mut [&'b mut [T]],
+) -> &'a mut [&'b mut [U]] {
+    assert_same::<T, U>();
+    unsafe { &mut *(slice as *mut [&'b mut [T]] as *mut [&'b mut [U]]) }
+}
+
+/// Coerces a reference to a possibly unsized `T`, such as `str` or a wrapper around it, into a
+/// reference to `U`. The pointer metadata is preserved.
+///
+/// This is the same as [`coerce_ref`], which accepts unsized types as well.
+///
+/// # Example
+/// ```
+/// use coe::coerce_str_ref;
+///
+/// #[repr(transparent)]
+/// struct Name(str);
+///
+/// fn as_name<T: ?Sized + 'static>(value: &T) -> &Name {
+///     coerce_str_ref(value)
+/// }
+///
+/// let name: &Name = unsafe { &*("coe" as *const str as *const Name) };
+/// assert_eq!(&as_name(name).0, "coe");
+/// ```
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_str_ref<T: ?Sized + 'static, U: ?Sized + 'static>(value: &T) -> &U {
+    coerce_ref(value)
+}
+
+/// Coerces each item of an iterator of `T` into `U`, checking the types only once.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_iter<I: Iterator, U: 'static>(iter: I) -> impl Iterator<Item = U>
+where
+    I::Item: 'static,
+{
+    assert_same::<I::Item, U>();
+    iter.map(|item| unsafe { coerce_unchecked(item) })
+}
+
+/// Coerces a function pointer taking a mutable slice of `T` into one taking a mutable slice of
+/// `U`.
+///
+/// Higher-ranked function pointers such as `fn(&mut [T])` can't be covered by the `Coerce` impl
+/// for `fn(T) -> R` without overlapping with it, so this shape is provided as a separate function.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_fn_ptr<T: 'static, U: 'static>(f: fn(&mut [T])) -> fn(&mut [U]) {
+    assert_same::<T, U>();
+    unsafe { transmute::<fn(&mut [T]), fn(&mut [U])>(f) }
+}
+
+/// Coerces a boxed slice of `T` into a boxed slice of `U`, reusing the same allocation.
+#[cfg(feature = "alloc")]
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_boxed_slice<T: 'static, U: 'static>(b: Box<[T]>) -> Box<[U]> {
+    b.coerce()
+}
+
+/// Coerces a boxed closure taking a mutable slice of `T` into one taking a mutable slice of `U`.
+#[cfg(feature = "alloc")]
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+#[allow(clippy::type_complexity)]
+pub fn coerce_closure<T: 'static, U: 'static>(
+    f: Box<dyn FnMut(&mut [T])>,
+) -> Box<dyn FnMut(&mut [U])> {
+    assert_same::<T, U>();
+    unsafe { transmute::<Box<dyn FnMut(&mut [T])>, Box<dyn FnMut(&mut [U])>>(f) }
+}
+
+/// Coerces a mutable reference to a `Vec` of `T` into a mutable reference to a `Vec` of `U`,
+/// so that a scratch buffer can be reused without moving it.
+#[cfg(feature = "alloc")]
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_vec_in_place<T: 'static, U: 'static>(v: &mut Vec<T>) -> &mut Vec<U> {
+    v.coerce()
+}
+
+/// Coerces a box of a possibly unsized `T` into a box of `U`, keeping the pointer metadata, e.g.
+/// for moving a `Box<str>` or a `Box<[T]>` generically.
+#[cfg(feature = "alloc")]
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_boxed_dst<T: ?Sized + 'static, U: ?Sized + 'static>(b: Box<T>) -> Box<U> {
+    assert_same::<T, U>();
+    unsafe {
+        Box::from_raw(core::mem::transmute_copy::<*mut T, *mut U>(&Box::into_raw(
+            b,
+        )))
+    }
+}
+
+/// Boxes `value` into a `Box<dyn Any>`, for passing it across dynamically typed boundaries.
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn into_any<T: 'static>(value: T) -> Box<dyn core::any::Any> {
+    Box::new(value)
+}
+
+/// Unboxes a value of type `U` from a `Box<dyn Any>`, or returns the box unchanged if it doesn't
+/// contain a `U`.
+#[cfg(feature = "alloc")]
+#[inline]
+pub fn from_any<U: 'static>(b: Box<dyn core::any::Any>) -> Result<U, Box<dyn core::any::Any>> {
+    b.downcast::<U>().map(|b| *b)
+}
+
+/// Returns a reference to the concrete value behind `value` if it is a `U`, or `None` otherwise.
+///
+/// This is the checked counterpart of [`coerce_ref`] for values whose type was erased behind a
+/// `&dyn Any`, where the type check reads the `TypeId` from the vtable at runtime instead of being
+/// folded away at compile time.
+#[inline]
+pub fn coerce_concrete_from_dyn<U: 'static>(value: &dyn core::any::Any) -> Option<&U> {
+    value.downcast_ref::<U>()
+}
+
+/// Checks in debug builds that `T` and `U` have the same size and alignment, as a last line of
+/// defense before reinterpreting one as the other.
+#[track_caller]
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+fn debug_assert_same_layout<T, U>() {
+    debug_assert_eq!(core::mem::size_of::<T>(), core::mem::size_of::<U>());
+    debug_assert_eq!(core::mem::align_of::<T>(), core::mem::align_of::<U>());
+}
+
+/// Similar to [`coerce`] but operates on any lifetime-free type.
+///
+/// The value is moved bit for bit, whatever its layout or niches, so its destructor runs exactly
+/// once, when the returned value is dropped.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn coerce_static<T: 'static, U: 'static>(value: T) -> U {
+    assert_same::<T, U>();
+    unsafe { reinterpret(value) }
+}
+
+/// Similar to [`coerce_static`] but restricted to `Copy` types, which lets the value be read
+/// directly through a pointer cast instead of being copied out of a `ManuallyDrop`.
+///
+/// Optimized builds usually produce the same code for both, but this one is easier on the
+/// optimizer at low optimization levels, e.g. for scalars such as `f64`.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_copy<T: 'static + Copy, U: 'static + Copy>(value: T) -> U {
+    assert_same::<T, U>();
+    unsafe { *(&value as *const T as *const U) }
+}
+
+/// Similar to [`coerce_static`] but returns the original value instead of panicking if `T` and
+/// `U` are not the same type.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn try_coerce_static<T: 'static, U: 'static>(value: T) -> Result<U, T> {
+    if is_same::<T, U>() {
+        Ok(unsafe { core::mem::transmute_copy(&core::mem::ManuallyDrop::new(value)) })
+    } else {
+        Err(value)
+    }
+}
+
+/// Same as [`try_coerce_static`], which doesn't contain any panicking code path.
+///
+/// Embedded users building with `panic = "abort"` should prefer either of them over the panicking
+/// coercion functions.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn coerce_static_catch<T: 'static, U: 'static>(value: T) -> Result<U, T> {
+    try_coerce_static(value)
+}
+
+/// Similar to [`try_coerce_static`], but also returns a [`TypeMismatch`] describing the two types
+/// along with the original value if they are not the same type.
+///
+/// # Example
+/// ```
+/// use coe::coerce_static_checked;
+///
+/// let (value, err) = coerce_static_checked::<u32, f64>(1).unwrap_err();
+/// assert_eq!(value, 1);
+/// assert_eq!(err.to_string(), "expected type `f64`, found type `u32`");
+/// ```
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_static_checked<T: 'static, U: 'static>(value: T) -> Result<U, (T, TypeMismatch)> {
+    try_coerce_static(value).map_err(|value| (value, TypeMismatch::new::<T, U>()))
+}
+
+/// Similar to [`coerce_static`] but calls `f` on the original value instead of panicking if `T`
+/// and `U` are not the same type.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn coerce_static_or_else<T: 'static, U: 'static, F: FnOnce(T) -> U>(value: T, f: F) -> U {
+    match try_coerce_static(value) {
+        Ok(value) => value,
+        Err(value) => f(value),
+    }
+}
+
+/// Similar to [`coerce_static`] but drops the original value and returns `U::default()` instead
+/// of panicking if `T` and `U` are not the same type.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn coerce_or_default<T: 'static, U: 'static + Default>(value: T) -> U {
+    coerce_static_or_else(value, |_| U::default())
+}
+
+/// Similar to [`coerce_static`] but, if `T` and `U` are not the same type, drops the original
+/// value and then calls `on_mismatch` with the names of `T` and `U` to produce the result instead
+/// of panicking, e.g. to log the mismatch and fall back to a replacement value.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn coerce_static_or<T: 'static, U: 'static>(
+    value: T,
+    on_mismatch: impl FnOnce(&'static str, &'static str) -> U,
+) -> U {
+    match try_coerce_static(value) {
+        Ok(value) => value,
+        Err(value) => {
+            drop(value);
+            on_mismatch(type_name::<T>(), type_name::<U>())
+        }
+    }
+}
+
+/// Similar to [`try_coerce_static`] but only coerces if `id` is the `TypeId` of both `T` and `U`,
+/// and returns the original value otherwise.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn coerce_if_type<T: 'static, U: 'static>(id: TypeId, value: T) -> Result<U, T> {
+    if is_type::<T>(id) && is_type::<U>(id) {
+        try_coerce_static(value)
+    } else {
+        Err(value)
+    }
+}
+
+/// Coerces a value of type `T` into a value of type `U`, without checking that they are the same
+/// type.
+///
+/// # Safety
+/// `T` and `U` must be the same type, including any lifetimes they contain.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub unsafe fn coerce_unchecked<T, U>(value: T) -> U {
+    reinterpret(value)
+}
+
+/// Coerces a reference to `T` into a reference to `U`, without checking that they are the same
+/// type.
+///
+/// # Safety
+/// `T` and `U` must be the same type, including any lifetimes they contain.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub unsafe fn coerce_ref_unchecked<T, U>(value: &T) -> &U {
+    reinterpret_ref(value)
+}
+
+/// Coerces a mutable reference to `T` into a mutable reference to `U`, without checking that
+/// they are the same type.
+///
+/// # Safety
+/// `T` and `U` must be the same type, including any lifetimes they contain.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub unsafe fn coerce_mut_unchecked<T, U>(value: &mut T) -> &mut U {
+    reinterpret_mut(value)
+}
+
+/// Reinterprets a value of type `T` as a value of type `U`, without any type check.
+///
+/// This is the primitive underlying the coercion functions, for types that are known to be
+/// interchangeable by other means.
+///
+/// # Safety
+/// `T` and `U` must have the same size and alignment, and the value must be a valid `U`,
+/// including any lifetimes and invariants it carries. The value is moved into the result, and
+/// its destructor only runs as a `U`.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub unsafe fn reinterpret<T, U>(value: T) -> U {
+    debug_assert_same_layout::<T, U>();
+    core::mem::transmute_copy(&core::mem::ManuallyDrop::new(value))
+}
+
+/// Reinterprets a reference to `T` as a reference to `U`, without any type check.
+///
+/// # Safety
+/// Same as [`reinterpret`], for the referent.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub unsafe fn reinterpret_ref<T, U>(value: &T) -> &U {
+    debug_assert_same_layout::<T, U>();
+    &*(value as *const T as *const U)
+}
+
+/// Reinterprets a mutable reference to `T` as a mutable reference to `U`, without any type check.
+///
+/// # Safety
+/// Same as [`reinterpret`], for the referent. Additionally, any `U` written through the
+/// reference must be a valid `T`.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub unsafe fn reinterpret_mut<T, U>(value: &mut T) -> &mut U {
+    debug_assert_same_layout::<T, U>();
+    &mut *(value as *mut T as *mut U)
+}
+
+/// Dispatches on the concrete type of `T`, expanding to a chain of [`is_same`] checks.
+///
+/// The optional bindings are coerced to the matched type inside each arm. A plain identifier is
+/// coerced by value with [`coerce_static`], while `&name`, `&mut name`, `&[name]` and
+/// `&mut [name]` bindings are coerced with [`Coerce`]. The `_` arm runs when no type matches.
+///
+/// # Example
+/// ```
+/// use coe::match_type;
+///
+/// fn scale<T: 'static>(factor: T, slice: &mut [T]) {
+///     match_type!(T, (factor, &mut [slice]) => {
+///         u32 => {
+///             for x in slice {
+///                 *x *= 2 * factor;
+///             }
+///         }
+///         f64 => {
+///             for x in slice {
+///                 *x *= factor;
+///             }
+///         }
+///         _ => panic!("unsupported type"),
+///     })
+/// }
+///
+/// let mut ints = [0, 1, 2u32];
+/// let mut floats = [0.0, 1.0, 2.0f64];
+/// scale(2, &mut ints);
+/// scale(2.0, &mut floats);
+/// assert_eq!(ints, [0, 4, 8]);
+/// assert_eq!(floats, [0.0, 2.0, 4.0]);
+/// ```
+#[macro_export]
+macro_rules! match_type {
+    ($T: ty => { $($arms: tt)* }) => {
+        $crate::match_type!(@arms $T, (); $($arms)*)
+    };
+    ($T: ty, ($($bindings: tt)*) => { $($arms: tt)* }) => {
+        $crate::match_type!(@arms $T, ($($bindings)*); $($arms)*)
+    };
+
+    (@arms $T: ty, $bindings: tt;) => {
+        ()
+    };
+    (@arms $T: ty, $bindings: tt; _ => $fallback: expr $(,)?) => {
+        $fallback
+    };
+    (@arms $T: ty, $bindings: tt; $ty: ty => $body: expr, $($rest: tt)*) => {
+        if $crate::is_same::<$T, $ty>() {
+            $crate::match_type!(@bind $ty, $bindings);
+            $body
+        } else {
+            $crate::match_type!(@arms $T, $bindings; $($rest)*)
+        }
+    };
+    (@arms $T: ty, $bindings: tt; $ty: ty => $body: block $($rest: tt)*) => {
+        $crate::match_type!(@arms $T, $bindings; $ty => $body, $($rest)*)
+    };
+    (@arms $T: ty, $bindings: tt; $ty: ty => $body: expr) => {
+        $crate::match_type!(@arms $T, $bindings; $ty => $body,)
+    };
+
+    (@bind $ty: ty, ()) => {};
+    (@bind $ty: ty, (&mut [$name: ident] $(, $($rest: tt)*)?)) => {
+        let $name: &mut [$ty] = $crate::coerce($name);
+        $crate::match_type!(@bind $ty, ($($($rest)*)?));
+    };
+    (@bind $ty: ty, (&[$name: ident] $(, $($rest: tt)*)?)) => {
+        let $name: &[$ty] = $crate::coerce($name);
+        $crate::match_type!(@bind $ty, ($($($rest)*)?));
+    };
+    (@bind $ty: ty, (&mut $name: ident $(, $($rest: tt)*)?)) => {
+        let $name: &mut $ty = $crate::coerce($name);
+        $crate::match_type!(@bind $ty, ($($($rest)*)?));
+    };
+    (@bind $ty: ty, (&$name: ident $(, $($rest: tt)*)?)) => {
+        let $name: &$ty = $crate::coerce($name);
+        $crate::match_type!(@bind $ty, ($($($rest)*)?));
+    };
+    (@bind $ty: ty, ($name: ident $(, $($rest: tt)*)?)) => {
+        let $name: $ty = $crate::coerce_static($name);
+        $crate::match_type!(@bind $ty, ($($($rest)*)?));
+    };
+}
+
+/// Witness that `T` and `U` are the same type.
+///
+/// The type check is performed once when the witness is created, after which the coercion
+/// methods are infallible and skip the runtime check entirely.
+///
+/// # Example
+/// ```
+/// use coe::TypeEq;
+///
+/// fn double<T: 'static>(slices: &mut [&mut [T]]) {
+///     if let Some(eq) = TypeEq::<T, f64>::new() {
+///         for slice in slices {
+///             for x in eq.coerce_slice_mut(slice) {
+///                 *x *= 2.0;
+///             }
+///         }
+///     }
+/// }
+///
+/// let mut a = [1.0, 2.0];
+/// let mut b = [3.0];
+/// double(&mut [&mut a, &mut b]);
+/// assert_eq!((a, b), ([2.0, 4.0], [6.0]));
+/// ```
+pub struct TypeEq<T, U>(core::marker::PhantomData<(Invariant<T>, Invariant<U>)>);
+
+type Invariant<T> = fn(T) -> T;
+
+impl<T, U> Copy for TypeEq<T, U> {}
+impl<T, U> Clone for TypeEq<T, U> {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, U> fmt::Debug for TypeEq<T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TypeEq<{}, {}>", type_name::<T>(), type_name::<U>())
+    }
+}
+
+impl<T: 'static, U: 'static> TypeEq<T, U> {
+    /// Returns a witness if `T` and `U` are the same type, or `None` otherwise.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn new() -> Option<Self> {
+        if is_same::<T, U>() {
+            Some(Self(core::marker::PhantomData))
+        } else {
+            None
+        }
+    }
+
+    /// Returns a witness if `T` and `U` are the same type, and panics otherwise.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    pub fn assert() -> Self {
+        assert_same::<T, U>();
+        Self(core::marker::PhantomData)
+    }
+}
+
+impl<T, U> TypeEq<T, U> {
+    /// Returns the witness for the reverse equality.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn inverse(self) -> TypeEq<U, T> {
+        TypeEq(core::marker::PhantomData)
+    }
+
+    /// Coerces a value of `T` into a value of `U`.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn coerce(self, value: T) -> U {
+        unsafe { core::mem::transmute_copy(&core::mem::ManuallyDrop::new(value)) }
+    }
+
+    /// Coerces a reference to `T` into a reference to `U`.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn coerce_ref(self, value: &T) -> &U {
+        unsafe { &*(value as *const T as *const U) }
+    }
+
+    /// Coerces a mutable reference to `T` into a mutable reference to `U`.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn coerce_mut(self, value: &mut T) -> &mut U {
+        unsafe { &mut *(value as *mut T as *mut U) }
+    }
+
+    /// Coerces a slice of `T` into a slice of `U`.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn coerce_slice(self, slice: &[T]) -> &[U] {
+        unsafe { &*(slice as *const [T] as *const [U]) }
+    }
+
+    /// Coerces a mutable slice of `T` into a mutable slice of `U`.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn coerce_slice_mut(self, slice: &mut [T]) -> &mut [U] {
+        unsafe { &mut *(slice as *mut [T] as *mut [U]) }
+    }
+}
+
+/// Value of type `T` stored along with the witness that `T` and `U` are the same type, so that it
+/// can be accessed as a `U` repeatedly without any further runtime check.
+///
+/// # Example
+/// ```
+/// use coe::CoerceCell;
+///
+/// struct Accumulator<T: 'static> {
+///     sum: Result<CoerceCell<T, f64>, T>,
+/// }
+///
+/// impl<T: 'static> Accumulator<T> {
+///     fn add(&mut self, x: f64) {
+///         if let Ok(sum) = &mut self.sum {
+///             *sum.get_mut() += x;
+///         }
+///     }
+/// }
+///
+/// let mut acc = Accumulator { sum: CoerceCell::new(0.0f64) };
+/// acc.add(1.5);
+/// acc.add(2.0);
+/// assert_eq!(*acc.sum.unwrap().get(), 3.5);
+/// ```
+pub struct CoerceCell<T, U> {
+    value: T,
+    eq: TypeEq<T, U>,
+}
+
+impl<T: fmt::Debug, U> fmt::Debug for CoerceCell<T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("CoerceCell").field(&self.value).finish()
+    }
+}
+
+impl<T: 'static, U: 'static> CoerceCell<T, U> {
+    /// Stores `value` if `T` and `U` are the same type, or returns it otherwise.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn new(value: T) -> Result<Self, T> {
+        match TypeEq::new() {
+            Some(eq) => Ok(Self { value, eq }),
+            None => Err(value),
+        }
+    }
+}
+
+impl<T, U> CoerceCell<T, U> {
+    /// Returns the witness that `T` and `U` are the same type.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn witness(&self) -> TypeEq<T, U> {
+        self.eq
+    }
+
+    /// Returns a reference to the stored value as a `U`.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn get(&self) -> &U {
+        self.eq.coerce_ref(&self.value)
+    }
+
+    /// Returns a mutable reference to the stored value as a `U`.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn get_mut(&mut self) -> &mut U {
+        self.eq.coerce_mut(&mut self.value)
+    }
+
+    /// Returns a reference to the stored value as a `T`.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn get_inner(&self) -> &T {
+        &self.value
+    }
+
+    /// Returns the stored value as a `U`.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn into_coerced(self) -> U {
+        self.eq.coerce(self.value)
+    }
+
+    /// Returns the stored value as a `T`.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+/// Witness that the const generic parameters `N` and `M` are equal, complementing [`TypeEq`] for
+/// array lengths.
+///
+/// # Example
+/// ```
+/// use coe::ConstEq;
+///
+/// fn first_four<const N: usize>(arr: &[f64; N]) -> Option<&[f64; 4]> {
+///     ConstEq::<N, 4>::new().map(|eq| eq.coerce_ref(arr))
+/// }
+///
+/// assert_eq!(first_four(&[1.0; 4]), Some(&[1.0; 4]));
+/// assert_eq!(first_four(&[1.0; 3]), None);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ConstEq<const N: usize, const M: usize>(());
+
+impl<const N: usize, const M: usize> ConstEq<N, M> {
+    /// Returns a witness if `N` and `M` are equal, or `None` otherwise.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn new() -> Option<Self> {
+        if N == M {
+            Some(Self(()))
+        } else {
+            None
+        }
+    }
+
+    /// Returns a witness if `N` and `M` are equal, and panics otherwise.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    pub fn assert() -> Self {
+        assert_eq!(N, M);
+        Self(())
+    }
+
+    /// Returns the witness for the reverse equality.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn inverse(self) -> ConstEq<M, N> {
+        ConstEq(())
+    }
+
+    /// Coerces an array of length `N` into an array of length `M`.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn coerce<U>(self, arr: [U; N]) -> [U; M] {
+        unsafe { core::mem::transmute_copy(&ManuallyDrop::new(arr)) }
+    }
+
+    /// Coerces a reference to an array of length `N` into a reference to an array of length `M`.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn coerce_ref<U>(self, arr: &[U; N]) -> &[U; M] {
+        unsafe { &*(arr as *const [U; N] as *const [U; M]) }
+    }
+
+    /// Coerces a mutable reference to an array of length `N` into a mutable reference to an array
+    /// of length `M`.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn coerce_mut<U>(self, arr: &mut [U; N]) -> &mut [U; M] {
+        unsafe { &mut *(arr as *mut [U; N] as *mut [U; M]) }
+    }
+}
+
+/// Trait for registering slice handlers keyed by their element type.
+///
+/// # Example
+/// ```
+/// use coe::{slice_handler, Specialize};
+///
+/// struct Double;
+///
+/// impl Specialize for Double {
+///     fn specialize<T: 'static>() -> Option<fn(&mut [T])> {
+///         slice_handler::<T, f64>(|slice| slice.iter_mut().for_each(|x| *x *= 2.0))
+///             .or_else(|| slice_handler::<T, u32>(|slice| slice.iter_mut().for_each(|x| *x *= 2)))
+///     }
+/// }
+///
+/// let mut floats = [1.0, 2.0f64];
+/// let mut ints = [1, 2u32];
+/// let mut bytes = [1, 2u8];
+/// assert!(Double::dispatch(&mut floats));
+/// assert!(Double::dispatch(&mut ints));
+/// assert!(!Double::dispatch(&mut bytes));
+/// assert_eq!(floats, [2.0, 4.0]);
+/// assert_eq!(ints, [2, 4]);
+/// assert_eq!(bytes, [1, 2]);
+/// ```
+pub trait Specialize {
+    /// Returns the handler for slices of `T`, or `None` if there is none.
+    fn specialize<T: 'static>() -> Option<fn(&mut [T])>;
+
+    /// Runs the handler for slices of `T` on `slice`. Returns `false` if there is none.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    fn dispatch<T: 'static>(slice: &mut [T]) -> bool {
+        match Self::specialize::<T>() {
+            Some(handler) => {
+                handler(slice);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Returns `handler` as a handler for slices of `T` if `T` and `U` are the same type, or `None`
+/// otherwise.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn slice_handler<T: 'static, U: 'static>(handler: fn(&mut [U])) -> Option<fn(&mut [T])> {
+    try_coerce_static(handler).ok()
+}
+
+/// Calls the handler of the first arm whose type is the element type of the given mutable slice,
+/// with the slice coerced to that type, and returns its result from the enclosing function. Falls
+/// through if no arm matches.
+///
+/// # Example
+/// ```
+/// use coe::specialize_slice;
+///
+/// fn double_f64(slice: &mut [f64]) {
+///     slice.iter_mut().for_each(|x| *x *= 2.0);
+/// }
+///
+/// fn double<T: 'static + Copy + core::ops::Add<Output = T>>(slice: &mut [T]) {
+///     specialize_slice!(slice: {
+///         f64 => double_f64,
+///         u32 => |slice: &mut [u32]| slice.iter_mut().for_each(|x| *x <<= 1),
+///     });
+///     for x in slice {
+///         *x = *x + *x;
+///     }
+/// }
+///
+/// let mut floats = [1.0, 2.0f64];
+/// let mut bytes = [1, 2u8];
+/// double(&mut floats);
+/// double(&mut bytes);
+/// assert_eq!(floats, [2.0, 4.0]);
+/// assert_eq!(bytes, [2, 4]);
+/// ```
+#[macro_export]
+macro_rules! specialize_slice {
+    ($slice: ident : { $($ty: ty => $handler: expr),* $(,)? }) => {
+        $(
+            if let Ok(slice) = $crate::TryCoerce::<&mut [$ty]>::try_coerce(&mut *$slice) {
+                return ($handler)(slice);
+            }
+        )*
+    };
+}
+
+/// Calls `fa` with `value` coerced to `A` if `T` and `A` are the same type, otherwise calls `fb`
+/// with `value` coerced to `B` if `T` and `B` are the same type, otherwise calls `fallback` with
+/// the original value.
+///
+/// # Example
+/// ```
+/// use coe::specialize_two;
+///
+/// fn describe<T: 'static + core::fmt::Debug>(value: T) -> String {
+///     specialize_two(
+///         value,
+///         |x: f64| format!("float {x}"),
+///         |x: u32| format!("int {x}"),
+///         |x| format!("other {x:?}"),
+///     )
+/// }
+///
+/// assert_eq!(describe(1.5f64), "float 1.5");
+/// assert_eq!(describe(2u32), "int 2");
+/// assert_eq!(describe('c'), "other 'c'");
+/// ```
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn specialize_two<T: 'static, A: 'static, B: 'static, R>(
+    value: T,
+    fa: impl FnOnce(A) -> R,
+    fb: impl FnOnce(B) -> R,
+    fallback: impl FnOnce(T) -> R,
+) -> R {
+    match try_coerce_static(value) {
+        Ok(value) => fa(value),
+        Err(value) => match try_coerce_static(value) {
+            Ok(value) => fb(value),
+            Err(value) => fallback(value),
+        },
+    }
+}
+
+/// Returns `true` if all the given types are the same type.
+///
+/// # Example
+/// ```
+/// use coe::is_same_all;
+///
+/// assert!(is_same_all!(f64, f64, f64));
+/// assert!(!is_same_all!(f64, f64, u32));
+/// ```
+#[macro_export]
+macro_rules! is_same_all {
+    ($first: ty $(, $rest: ty)+ $(,)?) => {
+        true $(&& $crate::is_same::<$first, $rest>())+
+    };
+}
+
+/// Checks if all the given types are the same type, and panics if that's not the case, naming
+/// the first type that doesn't match the first one.
+#[macro_export]
+macro_rules! assert_same_all {
+    ($first: ty $(, $rest: ty)+ $(,)?) => {
+        $($crate::assert_same::<$rest, $first>();)+
+    };
+}
+
+/// Returns the index of the first candidate type that is the same type as the given one, or
+/// `None` if there is none.
+///
+/// # Example
+/// ```
+/// use coe::is_any_of;
+///
+/// assert_eq!(is_any_of!(f64; f32, f64, u32), Some(1));
+/// assert_eq!(is_any_of!(u8; f32, f64, u32), None);
+/// ```
+#[macro_export]
+macro_rules! is_any_of {
+    ($ty: ty; $($candidate: ty),+ $(,)?) => {
+        [$($crate::is_same::<$ty, $candidate>()),+]
+            .iter()
+            .position(|&same| same)
+    };
+}
+
+/// Type-erased slice kernel, associated with the element type it operates on.
+#[derive(Copy, Clone, Debug)]
+pub struct SliceKernel {
+    id: TypeId,
+    kernel: unsafe fn(),
+}
+
+impl SliceKernel {
+    /// Wraps a kernel operating on slices of `U`.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn new<U: 'static>(kernel: fn(&mut [U])) -> Self {
+        Self {
+            id: TypeId::of::<U>(),
+            kernel: unsafe { transmute::<fn(&mut [U]), unsafe fn()>(kernel) },
+        }
+    }
+
+    /// Returns the `TypeId` of the element type the kernel operates on.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn type_id(&self) -> TypeId {
+        self.id
+    }
+}
+
+/// Runs the first kernel in `table` that operates on slices of `T`. Returns `false` if there is
+/// none, in which case the caller is expected to run its fallback implementation.
+///
+/// # Example
+/// ```
+/// use coe::{dispatch_slice, SliceKernel};
+///
+/// fn double<T: 'static + Copy + core::ops::Add<Output = T>>(slice: &mut [T]) {
+///     let table = [
+///         SliceKernel::new::<f64>(|slice| slice.iter_mut().for_each(|x| *x *= 2.0)),
+///         SliceKernel::new::<u32>(|slice| slice.iter_mut().for_each(|x| *x <<= 1)),
+///     ];
+///     if !dispatch_slice(slice, &table) {
+///         for x in slice {
+///             *x = *x + *x;
+///         }
+///     }
+/// }
+///
+/// let mut floats = [1.0, 2.0f64];
+/// let mut bytes = [1, 2u8];
+/// double(&mut floats);
+/// double(&mut bytes);
+/// assert_eq!(floats, [2.0, 4.0]);
+/// assert_eq!(bytes, [2, 4]);
+/// ```
+#[inline]
+pub fn dispatch_slice<T: 'static>(slice: &mut [T], table: &[SliceKernel]) -> bool {
+    let id = TypeId::of::<T>();
+    match table.iter().find(|entry| entry.id == id) {
+        Some(entry) => {
+            let kernel = unsafe { transmute::<unsafe fn(), fn(&mut [T])>(entry.kernel) };
+            kernel(slice);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Lookup table associating types with values, such as kernels or enum tags, built once and
+/// queried by generic type.
+///
+/// # Example
+/// ```
+/// use coe::SpecializeDispatch;
+/// use core::any::TypeId;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Kernel {
+///     Float,
+///     Int,
+/// }
+///
+/// let entries = [(TypeId::of::<f64>(), Kernel::Float), (TypeId::of::<u32>(), Kernel::Int)];
+/// let table = SpecializeDispatch::new(&entries);
+/// assert_eq!(table.get::<u32>(), Some(&Kernel::Int));
+/// assert_eq!(table.get::<u8>(), None);
+/// ```
+#[derive(Copy, Clone, Debug)]
+pub struct SpecializeDispatch<'a, R> {
+    entries: &'a [(TypeId, R)],
+}
+
+impl<'a, R> SpecializeDispatch<'a, R> {
+    /// Creates a table from `(TypeId, value)` pairs. If a type appears several times, the first
+    /// entry is used.
+    #[inline]
+    pub const fn new(entries: &'a [(TypeId, R)]) -> Self {
+        Self { entries }
+    }
+
+    /// Returns the value associated with `T`, or `None` if there is none.
+    #[inline]
+    pub fn get<T: ?Sized + 'static>(&self) -> Option<&'a R> {
+        let id = TypeId::of::<T>();
+        self.entries
+            .iter()
+            .find(|(entry, _)| *entry == id)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the entries of the table.
+    #[inline]
+    pub fn entries(&self) -> &'a [(TypeId, R)] {
+        self.entries
+    }
+}
+
+/// Cached identifier of a type, for comparing the same type against many candidates.
+///
+/// # Example
+/// ```
+/// use coe::TypeKey;
+///
+/// fn index<T: 'static>() -> Option<usize> {
+///     let key = TypeKey::of::<T>();
+///     [TypeKey::of::<f32>(), TypeKey::of::<f64>()]
+///         .iter()
+///         .position(|&candidate| candidate == key)
+/// }
+///
+/// assert_eq!(index::<f64>(), Some(1));
+/// assert_eq!(index::<u8>(), None);
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TypeKey(TypeId);
+
+impl TypeKey {
+    /// Returns the key of `T`.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn of<T: 'static + ?Sized>() -> Self {
+        Self(TypeId::of::<T>())
+    }
+
+    /// Returns `true` if this is the key of `U`.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    pub fn matches<U: 'static + ?Sized>(&self) -> bool {
+        self.0 == TypeId::of::<U>()
+    }
+}
+
+impl From<TypeId> for TypeKey {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    fn from(id: TypeId) -> Self {
+        Self(id)
+    }
+}
+
+impl From<TypeKey> for TypeId {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    fn from(key: TypeKey) -> Self {
+        key.0
+    }
+}
+
+/// Coerces a value into the given type when both types are known at the call site, without any
+/// runtime check.
+///
+/// This is meant for non-generic callers: if the two types differ, the mismatch is reported at
+/// compile time instead of panicking at runtime. In generic code, use [`coerce_static`] instead.
+///
+/// # Example
+/// ```
+/// use coe::coerce_same;
+///
+/// let x: f64 = coerce_same!(1.0f64, f64);
+/// assert_eq!(x, 1.0);
+/// ```
+///
+/// Mismatched types fail to compile:
+/// ```compile_fail,E0308
+/// use coe::coerce_same;
+///
+/// let x = coerce_same!(1u32, f64);
+/// ```
+///
+/// This includes types that would otherwise implicitly coerce into each other, such as through a
+/// deref coercion:
+/// ```compile_fail,E0308
+/// use coe::coerce_same;
+///
+/// let s = String::new();
+/// let x = coerce_same!(&s, &str);
+/// ```
+///
+/// an unsizing coercion:
+/// ```compile_fail,E0308
+/// use coe::coerce_same;
+///
+/// let x = coerce_same!(&[1.0f64; 2], &[f64]);
+/// ```
+///
+/// or a reborrow of a mutable reference as a shared one:
+/// ```compile_fail,E0308
+/// use coe::coerce_same;
+///
+/// let mut v = 1u32;
+/// let x = coerce_same!(&mut v, &u32);
+/// ```
+#[macro_export]
+macro_rules! coerce_same {
+    ($value: expr, $ty: ty $(,)?) => {
+        $crate::__same($value, ::core::marker::PhantomData::<$ty>)
+    };
+}
+
+// the type of `value` is inferred before the expected type is known, so unlike a `let` with a type
+// annotation, this doesn't allow any implicit coercion between the two types.
+#[doc(hidden)]
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+pub fn __same<T>(value: T, _: core::marker::PhantomData<T>) -> T {
+    value
+}
+
+/// Extension trait providing coercion methods on any type.
+pub trait CoerceExt {
+    /// Coerces `self` into `U` and applies `f` to it if `Self` and `U` are the same type, or
+    /// returns `None` otherwise.
+    ///
+    /// # Example
+    /// ```
+    /// use coe::CoerceExt;
+    ///
+    /// fn double<T: 'static>(value: T) -> Option<f64> {
+    ///     value.coerce_map(|x: f64| 2.0 * x)
+    /// }
+    ///
+    /// assert_eq!(double(1.5f64), Some(3.0));
+    /// assert_eq!(double(1u32), None);
+    /// ```
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    fn coerce_map<U: 'static, R>(self, f: impl FnOnce(U) -> R) -> Option<R>
+    where
+        Self: Sized + 'static,
+    {
+        try_coerce_static(self).ok().map(f)
+    }
+
+    /// Returns `self` as a reference to `U` if `Self` and `U` are the same type, or `None`
+    /// otherwise, similarly to `<dyn Any>::downcast_ref`.
+    ///
+    /// # Example
+    /// ```
+    /// use coe::CoerceExt;
+    ///
+    /// fn as_f64<T: 'static>(value: &T) -> Option<f64> {
+    ///     value.coerce_downcast_ref::<f64>().copied()
+    /// }
+    ///
+    /// assert_eq!(as_f64(&1.5f64), Some(1.5));
+    /// assert_eq!(as_f64(&1u32), None);
+    /// ```
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    fn coerce_downcast_ref<U: 'static>(&self) -> Option<&U>
+    where
+        Self: Sized + 'static,
+    {
+        self.try_coerce().ok()
+    }
+
+    /// Returns `self` as a mutable reference to `U` if `Self` and `U` are the same type, or `None`
+    /// otherwise, similarly to `<dyn Any>::downcast_mut`.
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    fn coerce_downcast_mut<U: 'static>(&mut self) -> Option<&mut U>
+    where
+        Self: Sized + 'static,
+    {
+        self.try_coerce().ok()
+    }
+
+    /// Coerces `self` into `U`, which can be given explicitly with a turbofish where inference
+    /// falls short, e.g. in method chains.
+    ///
+    /// # Example
+    /// ```
+    /// use coe::CoerceExt;
+    ///
+    /// fn sum<T: 'static>(slice: &[T]) -> f64 {
+    ///     slice.coerce_to::<&[f64]>().iter().sum()
+    /// }
+    ///
+    /// assert_eq!(sum(&[1.0, 2.0f64]), 3.0);
+    /// ```
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce_to<U>(self) -> U
+    where
+        Self: Sized + Coerce<U>,
+    {
+        self.coerce()
+    }
+}
+
+impl<T: ?Sized> CoerceExt for T {}
+
+/// Trait for constructing a value by coercing a value of type `T`, mirroring [`From`].
+///
+/// This is implemented for all `'static` types in terms of [`coerce_static`], and is convenient
+/// when the target type is known from context.
+///
+/// # Example
+/// ```
+/// use coe::CoerceFrom;
+///
+/// fn to_f64<T: 'static>(value: T) -> f64 {
+///     f64::coerce_from(value)
+/// }
+///
+/// assert_eq!(to_f64(1.5f64), 1.5);
+/// ```
+pub trait CoerceFrom<T>: Sized {
+    /// Coerces `value` into `Self`.
+    ///
+    /// # Panics
+    /// Panics if `T` and `Self` are not the same type.
+    fn coerce_from(value: T) -> Self;
+}
+
+/// Trait for coercing a value into a value of type `U`, mirroring [`Into`].
+///
+/// This is implemented for every `T` such that `U` implements [`CoerceFrom<T>`].
+///
+/// # Example
+/// ```
+/// use coe::CoerceInto;
+///
+/// fn to_f64<T: 'static>(value: T) -> f64 {
+///     value.coerce_into()
+/// }
+///
+/// assert_eq!(to_f64(1.5f64), 1.5);
+/// ```
+pub trait CoerceInto<U>: Sized {
+    /// Coerces `self` into `U`.
+    ///
+    /// # Panics
+    /// Panics if `Self` and `U` are not the same type.
+    fn coerce_into(self) -> U;
+}
+
+impl<T: 'static, U: 'static> CoerceFrom<T> for U {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce_from(value: T) -> Self {
+        coerce_static(value)
+    }
+}
+
+impl<T, U: CoerceFrom<T>> CoerceInto<U> for T {
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce_into(self) -> U {
+        U::coerce_from(self)
+    }
+}
+
+/// Marker trait for opting a generic newtype into coercion through [`coerce_transparent`].
+///
+/// # Safety
+/// The implementing type must be `#[repr(transparent)]` over a field whose type is its generic
+/// parameter `Inner`, and `With<U>` must be the same type with that parameter replaced by `U`, so
+/// that `Self::With<Self::Inner>` is `Self`.
+///
+/// # Example
+/// ```
+/// use coe::{coerce_transparent, TransparentCoerce};
+///
+/// #[repr(transparent)]
+/// struct Meters<T>(T);
+///
+/// unsafe impl<T: 'static> TransparentCoerce for Meters<T> {
+///     type Inner = T;
+///     type With<U: 'static> = Meters<U>;
+/// }
+///
+/// fn as_f64<T: 'static>(value: Meters<T>) -> Meters<f64> {
+///     coerce_transparent(value)
+/// }
+///
+/// assert_eq!(as_f64(Meters(1.5)).0, 1.5);
+/// ```
+pub unsafe trait TransparentCoerce: Sized {
+    /// Type of the wrapped field.
+    type Inner: 'static;
+    /// Same wrapper type, wrapping a `U` instead.
+    type With<U: 'static>;
+}
+
+/// Coerces a [`TransparentCoerce`] wrapper of `T` into the same wrapper of `U`.
+///
+/// # Panics
+/// Panics if the wrapped type and `U` are not the same type.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_transparent<W: TransparentCoerce, U: 'static>(value: W) -> W::With<U> {
+    assert_same::<W::Inner, U>();
+    // SAFETY: `W::With<U>` is `W::With<W::Inner>`, which is `W` by the contract of the trait.
+    unsafe { coerce_unchecked(value) }
+}
+
+/// Wrapper around a slice, such as a matrix view, that can be coerced into the same wrapper with a
+/// different element type through [`coerce_wrapper`], by taking it apart and rebuilding it around
+/// the coerced slice.
+///
+/// Unlike [`TransparentCoerce`], this is safe to implement, since the wrapper is only ever built
+/// with its own constructor.
+///
+/// # Example
+/// ```
+/// use coe::{coerce_wrapper, InnerCoerce};
+///
+/// struct Matrix<'a, T> {
+///     data: &'a [T],
+///     nrows: usize,
+/// }
+///
+/// impl<'a, T: 'static> InnerCoerce<'a> for Matrix<'a, T> {
+///     type Elem = T;
+///     type Parts = usize;
+///     type With<U: 'static> = Matrix<'a, U>;
+///
+///     fn into_parts(self) -> (&'a [T], usize) {
+///         (self.data, self.nrows)
+///     }
+///
+///     fn from_parts<U: 'static>(data: &'a [U], nrows: usize) -> Matrix<'a, U> {
+///         Matrix { data, nrows }
+///     }
+/// }
+///
+/// fn as_f64<T: 'static>(mat: Matrix<'_, T>) -> Matrix<'_, f64> {
+///     coerce_wrapper(mat)
+/// }
+///
+/// let mat = as_f64(Matrix { data: &[1.0, 2.0], nrows: 2 });
+/// assert_eq!((mat.data, mat.nrows), (&[1.0, 2.0][..], 2));
+/// ```
+pub trait InnerCoerce<'a>: Sized {
+    /// Type of the elements of the wrapped slice.
+    type Elem: 'static;
+    /// Remaining state of the wrapper, that doesn't depend on the element type.
+    type Parts;
+    /// Same wrapper type, wrapping a slice of `U` instead.
+    type With<U: 'static>;
+
+    /// Splits the wrapper into its slice and the rest of its state.
+    fn into_parts(self) -> (&'a [Self::Elem], Self::Parts);
+    /// Rebuilds the wrapper around a slice of `U`.
+    fn from_parts<U: 'static>(slice: &'a [U], parts: Self::Parts) -> Self::With<U>;
+}
+
+/// Coerces an [`InnerCoerce`] wrapper around a slice of `T` into the same wrapper around a slice
+/// of `U`.
+///
+/// # Panics
+/// Panics if the element type and `U` are not the same type.
+#[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+#[cfg_attr(feature = "no_inline_always", inline)]
+#[track_caller]
+pub fn coerce_wrapper<'a, W: InnerCoerce<'a>, U: 'static>(value: W) -> W::With<U> {
+    let (slice, parts) = value.into_parts();
+    W::from_parts(slice.coerce(), parts)
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl<T: ?Sized> Sealed for &T {}
+    impl<T: ?Sized> Sealed for &mut T {}
+}
+
+/// Shared or mutable reference, for coercing references in code that is generic over the kind of
+/// borrow. This trait is sealed, and implemented for `&T` and `&mut T`.
+///
+/// # Example
+/// ```
+/// use coe::CoerceBorrow;
+///
+/// fn as_f64<B: CoerceBorrow<Target = [T]>, T: 'static>(slice: B) -> B::With<[f64]> {
+///     slice.coerce_borrow()
+/// }
+///
+/// let mut data = [1.0, 2.0f64];
+/// assert_eq!(as_f64(&data[..]), [1.0, 2.0]);
+/// as_f64(&mut data[..])[0] = 3.0;
+/// assert_eq!(data, [3.0, 2.0]);
+/// ```
+pub trait CoerceBorrow: sealed::Sealed + Sized {
+    /// Type of the referent.
+    type Target: ?Sized + 'static;
+    /// Same kind of reference, with the same lifetime, to a `U` instead.
+    type With<U: ?Sized + 'static>;
+
+    /// Coerces the reference into the same kind of reference to `U`.
+    ///
+    /// # Panics
+    /// Panics if the referent and `U` are not the same type.
+    fn coerce_borrow<U: ?Sized + 'static>(self) -> Self::With<U>;
+}
+
+impl<'a, T: ?Sized + 'static> CoerceBorrow for &'a T {
+    type Target = T;
+    type With<U: ?Sized + 'static> = &'a U;
+
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce_borrow<U: ?Sized + 'static>(self) -> &'a U {
+        self.coerce()
+    }
+}
+
+impl<'a, T: ?Sized + 'static> CoerceBorrow for &'a mut T {
+    type Target = T;
+    type With<U: ?Sized + 'static> = &'a mut U;
+
+    #[cfg_attr(not(feature = "no_inline_always"), inline(always))]
+    #[cfg_attr(feature = "no_inline_always", inline)]
+    #[track_caller]
+    fn coerce_borrow<U: ?Sized + 'static>(self) -> &'a mut U {
+        self.coerce()
+    }
+}
+
+/// Guard checking that a checked coercion from `T` into `U` happened on the current thread while
+/// it was alive, and panicking on drop otherwise.
+///
+/// This is a testing aid for asserting that a generic function took its specialized branch rather
+/// than silently falling through to the generic one. Since checked coercions only succeed when
+/// both types are the same, `T` and `U` are usually the same type, naming the specialized branch.
+/// Coercions are recorded in [`assert_same`], so this covers [`Coerce`] and [`coerce_static`].
+///
+/// Requires the `specialization_guard` feature. When it's disabled, nothing is recorded.
+///
+/// # Example
+/// ```
+/// use coe::{Coerce, SpecializationGuard};
+///
+/// fn sum<T: 'static + Copy + Into<f64>>(values: &[T]) -> f64 {
+///     if coe::is_same::<T, f64>() {
+///         let values: &[f64] = values.coerce();
+///         values.iter().sum()
+///     } else {
+///         values.iter().map(|&x| x.into()).sum()
+///     }
+/// }
+///
+/// let guard = SpecializationGuard::<[f64], [f64]>::new();
+/// assert_eq!(sum(&[1.0, 2.0]), 3.0);
+/// assert_eq!(guard.count(), 1);
+/// ```
+#[cfg(feature = "specialization_guard")]
+pub struct SpecializationGuard<T: ?Sized + 'static, U: ?Sized + 'static> {
+    slot: usize,
+    location: &'static core::panic::Location<'static>,
+    // the slot lives in a thread local, so the guard must stay on its thread.
+    __marker: core::marker::PhantomData<(*const T, *const U)>,
+}
+
+#[cfg(feature = "specialization_guard")]
+mod specialization_guard {
+    use core::any::TypeId;
+    use core::cell::RefCell;
+    use std::vec::Vec;
+
+    std::thread_local! {
+        static SLOTS: RefCell<Vec<Option<(TypeId, TypeId, usize)>>> = const { RefCell::new(Vec::new()) };
+    }
+
+    pub(crate) fn open(from: TypeId, into: TypeId) -> usize {
+        SLOTS.with(|slots| {
+            let mut slots = slots.borrow_mut();
+            slots.push(Some((from, into, 0)));
+            slots.len() - 1
+        })
+    }
+
+    pub(crate) fn count(slot: usize) -> usize {
+        SLOTS.with(|slots| slots.borrow()[slot].map_or(0, |(_, _, count)| count))
+    }
+
+    pub(crate) fn close(slot: usize) -> usize {
+        SLOTS.with(|slots| {
+            let mut slots = slots.borrow_mut();
+            let count = slots[slot].take().map_or(0, |(_, _, count)| count);
+            while let Some(None) = slots.last() {
+                slots.pop();
+            }
+            count
+        })
+    }
+
+    #[inline(never)]
+    pub(crate) fn record(from: TypeId, into: TypeId) {
+        // the thread local may already be destroyed if this runs during thread teardown.
+        let _ = SLOTS.try_with(|slots| {
+            for (slot_from, slot_into, count) in slots.borrow_mut().iter_mut().flatten() {
+                if *slot_from == from && *slot_into == into {
+                    *count += 1;
+                }
+            }
+        });
+    }
+}
+
+#[cfg(feature = "specialization_guard")]
+impl<T: ?Sized + 'static, U: ?Sized + 'static> SpecializationGuard<T, U> {
+    /// Starts recording the coercions from `T` into `U` on the current thread.
+    #[inline]
+    #[track_caller]
+    pub fn new() -> Self {
+        Self {
+            slot: specialization_guard::open(TypeId::of::<T>(), TypeId::of::<U>()),
+            location: core::panic::Location::caller(),
+            __marker: core::marker::PhantomData,
+        }
+    }
+
+    /// Returns the number of coercions from `T` into `U` recorded so far.
+    #[inline]
+    pub fn count(&self) -> usize {
+        specialization_guard::count(self.slot)
+    }
+}
+
+#[cfg(feature = "specialization_guard")]
+impl<T: ?Sized + 'static, U: ?Sized + 'static> Default for SpecializationGuard<T, U> {
+    #[inline]
+    #[track_caller]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "specialization_guard")]
+impl<T: ?Sized + 'static, U: ?Sized + 'static> Drop for SpecializationGuard<T, U> {
+    fn drop(&mut self) {
+        let count = specialization_guard::close(self.slot);
+        if count == 0 && !std::thread::panicking() {
+            // drop glue doesn't forward the caller location, so point at where the guard was created.
+            panic!(
+                "specialization not taken: no coercion from {} into {}, for the guard created at {}",
+                type_name::<T>(),
+                type_name::<U>(),
+                self.location,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+    struct DropCounter(&'static AtomicUsize);
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Relaxed);
+        }
+    }
+
+    // runs `f`, which must fail to coerce `T` into `U`, and checks its panic message, so that the
+    // format of the message is only spelled out here.
+    #[track_caller]
+    #[cfg_attr(feature = "minimal_panic", allow(clippy::extra_unused_type_parameters))]
+    fn assert_mismatch<T: ?Sized + 'static, U: ?Sized + 'static>(
+        context: Option<&str>,
+        f: impl FnOnce(),
+    ) {
+        extern crate std;
+        use std::string::String;
+
+        let payload = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f))
+            .expect_err("expected the coercion to panic");
+        let message = match payload.downcast_ref::<String>() {
+            Some(message) => message.as_str(),
+            None => payload.downcast_ref::<&str>().unwrap(),
+        };
+
+        #[cfg(feature = "minimal_panic")]
+        let expected = {
+            let _ = context;
+            String::from("type mismatch")
+        };
+        #[cfg(not(feature = "minimal_panic"))]
+        let expected = {
+            let expected = std::format!(
+                "coercion failed: {} is not {}",
+                type_name::<T>(),
+                type_name::<U>()
+            );
+            match context {
+                Some(context) => std::format!("{context}: {expected}"),
+                None => expected,
+            }
+        };
+        assert_eq!(message, expected);
+    }
+
+    #[test]
+    #[allow(clippy::assign_op_pattern)]
+    fn test_coerce() {
+        let mut ints = [0, 1, 2u32];
+        let mut floats = [0.0, 1.0, 2.0f64];
+
+        pub fn generic_fn<T: 'static>(factor: T, slice: &mut [T]) {
+            if is_same::<u32, T>() {
+                let slice: &mut [u32] = slice.coerce();
+                let factor: u32 = coerce_static(factor);
+                for x in slice {
+                    *x = 2 * factor * *x;
+                }
+            } else if is_same::<f64, T>() {
+                let slice: &mut [f64] = slice.coerce();
+                let factor: f64 = coerce_static(factor);
+                for x in slice {
+                    *x = factor * *x;
+                }
+            }
+        }
+
+        generic_fn(2, &mut ints);
+        generic_fn(2.0, &mut floats);
+
+        assert_eq!(ints, [0, 4, 8]);
+        assert_eq!(floats, [0.0, 2.0, 4.0]);
+    }
+
+    #[test]
+    fn test_try_coerce_static() {
+        extern crate std;
+        use std::string::String;
+
+        let value = String::from("coe");
+        let value: Result<u32, String> = try_coerce_static(value);
+        assert_eq!(value, Err(String::from("coe")));
+
+        let value: Result<String, String> = try_coerce_static(value.unwrap_err());
+        assert_eq!(value, Ok(String::from("coe")));
+    }
+
+    #[test]
+    fn test_coerce_array() {
+        fn generic_fn<T: 'static, const N: usize>(array: [T; N]) -> [u32; N] {
+            array.coerce()
+        }
+        assert_eq!(generic_fn([1, 2, 3u32]), [1, 2, 3]);
+        assert_eq!(generic_fn::<u32, 0>([]), []);
+
+        let array: [(); 3] = [(); 3].coerce();
+        assert_eq!(array, [(); 3]);
+        let array: [(); 0] = [(); 0].coerce();
+        assert_eq!(array, []);
+
+        let mut array = [1, 2, 3u32];
+        let array_ref: &[u32; 3] = (&array).coerce();
+        assert_eq!(array_ref, &[1, 2, 3]);
+        let array_mut: &mut [u32; 3] = (&mut array).coerce();
+        array_mut[0] = 4;
+        assert_eq!(array, [4, 2, 3]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_coerce_box() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        let boxed = Box::new(DropCounter(&DROPS));
+        let ptr = &*boxed as *const DropCounter;
+        let boxed: Box<DropCounter> = boxed.coerce();
+        assert_eq!(&*boxed as *const DropCounter, ptr);
+        drop(boxed);
+        assert_eq!(DROPS.load(Relaxed), 1);
+
+        let boxed: Box<[DropCounter]> = Box::new([DropCounter(&DROPS), DropCounter(&DROPS)]);
+        let ptr = boxed.as_ptr();
+        let boxed: Box<[DropCounter]> = boxed.coerce();
+        assert_eq!(boxed.as_ptr(), ptr);
+        assert_eq!(boxed.len(), 2);
+        drop(boxed);
+        assert_eq!(DROPS.load(Relaxed), 3);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_coerce_vec() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        let mut vec = Vec::with_capacity(4);
+        vec.push(DropCounter(&DROPS));
+        vec.push(DropCounter(&DROPS));
+        let (ptr, capacity) = (vec.as_ptr(), vec.capacity());
+        let vec: Vec<DropCounter> = vec.coerce();
+        assert_eq!(vec.as_ptr(), ptr);
+        assert_eq!(vec.len(), 2);
+        assert_eq!(vec.capacity(), capacity);
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(vec);
+        assert_eq!(DROPS.load(Relaxed), 2);
+    }
+
+    #[test]
+    fn test_try_coerce() {
+        let mut ints = [0, 1, 2u32];
+
+        let slice: Result<&[f64], &[u32]> = ints[..].try_coerce();
+        assert_eq!(slice, Err(&[0, 1, 2u32][..]));
+        let slice: Result<&[u32], &[u32]> = ints[..].try_coerce();
+        assert_eq!(slice, Ok(&[0, 1, 2u32][..]));
+
+        let slice: Result<&mut [u32], &mut [u32]> = (&mut ints[..]).try_coerce();
+        slice.unwrap()[0] = 3;
+        assert_eq!(ints, [3, 1, 2]);
+
+        let value: Result<&mut f64, &mut u32> = (&mut ints[1]).try_coerce();
+        *value.unwrap_err() = 4;
+        let value: Result<&u32, &u32> = (&ints[1]).try_coerce();
+        assert_eq!(value, Ok(&4));
+    }
+
+    #[test]
+    fn test_match_type() {
+        fn name<T: 'static>() -> &'static str {
+            match_type!(T => {
+                f64 => "f64",
+                f32 => "f32",
+                _ => "other",
+            })
+        }
+        assert_eq!(name::<f64>(), "f64");
+        assert_eq!(name::<f32>(), "f32");
+        assert_eq!(name::<u8>(), "other");
+
+        fn sum<T: 'static>(init: T, slice: &[T]) -> f64 {
+            match_type!(T, (init, &[slice]) => {
+                u32 => slice.iter().fold(init, |acc, x| acc + x) as f64,
+                f64 => slice.iter().fold(init, |acc, x| acc + x),
+                _ => f64::NAN,
+            })
+        }
+        assert_eq!(sum(1u32, &[1, 2, 3]), 7.0);
+        assert_eq!(sum(0.5f64, &[1.0, 2.0]), 3.5);
+        assert!(sum(0u8, &[1, 2]).is_nan());
+
+        fn zero<T: 'static>(value: &mut T, slice: &mut [T]) {
+            match_type!(T, (&mut value, &mut [slice]) => {
+                u32 => {
+                    *value = 0;
+                    slice.fill(0);
+                }
+            })
+        }
+        let mut value = 1u32;
+        let mut slice = [1, 2u32];
+        zero(&mut value, &mut slice);
+        assert_eq!((value, slice), (0, [0, 0]));
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        extern crate std;
+        use std::string::ToString;
+
+        let err = TypeMismatch::new::<u32, f64>();
+        assert_eq!(err.expected(), "f64");
+        assert_eq!(err.found(), "u32");
+        assert_eq!(err.to_string(), "expected type `f64`, found type `u32`");
+    }
+
+    #[test]
+    fn test_assert_same_message() {
+        assert_mismatch::<u32, f64>(None, || {
+            assert_same::<u32, f64>();
+        });
+    }
+
+    #[test]
+    fn test_is_same_val() {
+        assert!(is_same_val(&1u32, &2u32));
+        assert!(!is_same_val(&1u32, &2.0f64));
+        assert_same_val(&[1.0f64], &[2.0f64]);
+    }
+
+    #[test]
+    fn test_assert_same_val() {
+        assert_mismatch::<u32, f64>(None, || {
+            assert_same_val(&1u32, &2.0f64);
+        });
+    }
+
+    #[test]
+    fn test_coerce_ptr() {
+        let null: *const f64 = core::ptr::null::<f64>().coerce();
+        assert!(null.is_null());
+        let null: *mut f64 = core::ptr::null_mut::<f64>().coerce();
+        assert!(null.is_null());
+
+        let dangling = core::ptr::NonNull::<u64>::dangling().as_ptr();
+        let coerced: *mut u64 = dangling.coerce();
+        assert_eq!(coerced, dangling);
+
+        let mut value = 1u32;
+        let ptr: *mut u32 = (&mut value as *mut u32).coerce();
+        unsafe { *ptr = 2 };
+        assert_eq!(value, 2);
+    }
+
+    #[cfg(feature = "nonstatic")]
+    #[test]
+    fn test_is_same_nonstatic() {
+        fn check<'a, 'b>(_: &'a str, _: &'b [u8]) {
+            assert!(is_same_nonstatic::<&'a str, &'static str>());
+            assert!(is_same_nonstatic::<&'a str, &'b str>());
+            assert!(is_same_nonstatic::<str, str>());
+            assert!(!is_same_nonstatic::<&'a str, &'b [u8]>());
+            assert!(!is_same_nonstatic::<&'a str, &'a mut str>());
+        }
+        check("", &[]);
+    }
+
+    #[test]
+    fn test_coerce_free_fns() {
+        let mut value = 1u32;
+        assert_eq!(*coerce_ref::<_, u32>(&value), 1);
+        *coerce_mut::<_, u32>(&mut value) = 2;
+        assert_eq!(value, 2);
+
+        let mut slice = [1.0, 2.0f64];
+        assert_eq!(coerce_slice::<_, f64>(&slice).iter().sum::<f64>(), 3.0);
+        coerce_slice_mut::<_, f64>(&mut slice)[0] = 3.0;
+        assert_eq!(slice, [3.0, 2.0]);
+    }
+
+    #[test]
+    fn test_coerce_slice_mismatch() {
+        assert_mismatch::<[u32], [f64]>(None, || {
+            coerce_slice::<_, f64>(&[1u32]);
+        });
+    }
+
+    #[test]
+    fn test_type_eq() {
+        assert!(TypeEq::<u32, f64>::new().is_none());
+
+        let eq = TypeEq::<u32, u32>::new().unwrap();
+        let mut value = 1u32;
+        let mut slice = [1, 2u32];
+        assert_eq!(eq.coerce(3), 3);
+        assert_eq!(*eq.coerce_ref(&value), 1);
+        *eq.coerce_mut(&mut value) = 2;
+        assert_eq!(value, 2);
+        assert_eq!(eq.coerce_slice(&slice), [1, 2]);
+        eq.inverse().coerce_slice_mut(&mut slice)[0] = 3;
+        assert_eq!(slice, [3, 2]);
+    }
+
+    #[test]
+    fn test_type_eq_assert() {
+        assert_mismatch::<u32, f64>(None, || {
+            TypeEq::<u32, f64>::assert();
+        });
+    }
+
+    #[test]
+    fn test_coerce_option_result() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        let none: Option<DropCounter> = None::<DropCounter>.coerce();
+        assert!(none.is_none());
+        let some: Option<DropCounter> = Some(DropCounter(&DROPS)).coerce();
+        assert!(some.is_some());
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(some);
+        assert_eq!(DROPS.load(Relaxed), 1);
+
+        let ok: Result<DropCounter, u32> = Ok::<_, u32>(DropCounter(&DROPS)).coerce();
+        assert!(ok.is_ok());
+        drop(ok);
+        assert_eq!(DROPS.load(Relaxed), 2);
+
+        let err: Result<u32, DropCounter> = Err::<u32, _>(DropCounter(&DROPS)).coerce();
+        assert!(err.is_err());
+        assert_eq!(DROPS.load(Relaxed), 2);
+        drop(err);
+        assert_eq!(DROPS.load(Relaxed), 3);
+    }
+
+    #[test]
+    fn test_coerce_result_mismatch() {
+        assert_mismatch::<u32, f64>(None, || {
+            let _: Result<u8, f64> = Ok::<u8, u32>(0).coerce();
+        });
+    }
+
+    #[test]
+    fn test_coerce_array_chunks() {
+        let slice = [1.0, 2.0, 3.0, 4.0, 5.0f64];
+        let (chunks, tail) = coerce_array_chunks::<_, f64, 2>(&slice);
+        assert_eq!(chunks, [[1.0, 2.0], [3.0, 4.0]]);
+        assert_eq!(tail, [5.0]);
+
+        let (chunks, tail) = coerce_array_chunks::<_, f64, 5>(&slice);
+        assert_eq!(chunks, [[1.0, 2.0, 3.0, 4.0, 5.0]]);
+        assert!(tail.is_empty());
+
+        let (chunks, tail) = coerce_array_chunks::<f64, f64, 4>(&[]);
+        assert!(chunks.is_empty());
+        assert!(tail.is_empty());
+    }
+
+    #[test]
+    fn test_specialize() {
+        struct Fill;
+        impl Specialize for Fill {
+            fn specialize<T: 'static>() -> Option<fn(&mut [T])> {
+                slice_handler::<T, u32>(|slice| slice.fill(1))
+                    .or_else(|| slice_handler::<T, f64>(|slice| slice.fill(2.0)))
+            }
+        }
+
+        let mut ints = [0u32; 2];
+        let mut floats = [0.0f64; 2];
+        let mut bytes = [0u8; 2];
+        assert!(Fill::dispatch(&mut ints));
+        assert!(Fill::dispatch(&mut floats));
+        assert!(!Fill::dispatch(&mut bytes));
+        assert!(Fill::specialize::<u8>().is_none());
+        assert_eq!(ints, [1, 1]);
+        assert_eq!(floats, [2.0, 2.0]);
+        assert_eq!(bytes, [0, 0]);
+    }
+
+    #[test]
+    fn test_assert_same_msg() {
+        assert_mismatch::<u32, f64>(Some("scaling kernel"), || {
+            assert_same_msg::<u32, u32>("unreachable");
+            assert_same_msg::<u32, f64>("scaling kernel");
+        });
+    }
+
+    #[test]
+    fn test_coerce_tuple() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        fn generic_fn<A: 'static, B: 'static>(tuple: (A, B)) -> (f64, usize) {
+            tuple.coerce()
+        }
+        assert_eq!(generic_fn((1.0, 2usize)), (1.0, 2));
+
+        let tuple: (u8, f64, u32) = (1u8, 2.0f64, 3u32).coerce();
+        assert_eq!(tuple, (1, 2.0, 3));
+
+        let tuple: (u8, DropCounter, u32, DropCounter) =
+            (1u8, DropCounter(&DROPS), 3u32, DropCounter(&DROPS)).coerce();
+        assert_eq!((tuple.0, tuple.2), (1, 3));
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(tuple);
+        assert_eq!(DROPS.load(Relaxed), 2);
+    }
+
+    #[test]
+    fn test_coerce_tuple_mismatch() {
+        assert_mismatch::<u32, f64>(None, || {
+            let _: (u8, f64) = (0u8, 0u32).coerce();
+        });
+    }
+
+    #[cfg(feature = "const_typeid")]
+    #[test]
+    fn test_is_same_const() {
+        const SAME: bool = is_same_const::<f64, f64>();
+        const DIFFERENT: bool = is_same_const::<f64, u32>();
+        const { assert!(SAME) };
+        const { assert!(!DIFFERENT) };
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_coerce_rc_arc() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        let rc = Rc::new(DropCounter(&DROPS));
+        let clone = rc.clone();
+        let coerced: Rc<DropCounter> = rc.coerce();
+        assert!(Rc::ptr_eq(&coerced, &clone));
+        assert_eq!(Rc::strong_count(&coerced), 2);
+        drop(clone);
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(coerced);
+        assert_eq!(DROPS.load(Relaxed), 1);
+
+        let arc = Arc::new(DropCounter(&DROPS));
+        let weak = Arc::downgrade(&arc);
+        let clone = arc.clone();
+        let coerced: Arc<DropCounter> = arc.coerce();
+        assert!(Arc::ptr_eq(&coerced, &clone));
+        assert_eq!(Arc::strong_count(&coerced), 2);
+        assert_eq!(Arc::weak_count(&coerced), 1);
+        drop(clone);
+        assert_eq!(DROPS.load(Relaxed), 1);
+        drop(coerced);
+        assert_eq!(DROPS.load(Relaxed), 2);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_coerce_rc_arc_slice() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        let rc: Rc<[DropCounter]> = Rc::new([DropCounter(&DROPS), DropCounter(&DROPS)]);
+        let clone = rc.clone();
+        let coerced: Rc<[DropCounter]> = rc.coerce();
+        assert!(Rc::ptr_eq(&coerced, &clone));
+        assert_eq!(coerced.len(), 2);
+        assert_eq!(Rc::strong_count(&coerced), 2);
+        drop(clone);
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(coerced);
+        assert_eq!(DROPS.load(Relaxed), 2);
+
+        let arc: Arc<[DropCounter]> = Arc::new([DropCounter(&DROPS), DropCounter(&DROPS)]);
+        let weak = Arc::downgrade(&arc);
+        let clone = arc.clone();
+        let coerced: Arc<[DropCounter]> = arc.coerce();
+        assert!(Arc::ptr_eq(&coerced, &clone));
+        assert_eq!(coerced.len(), 2);
+        assert_eq!(Arc::strong_count(&coerced), 2);
+        assert_eq!(Arc::weak_count(&coerced), 1);
+        drop(clone);
+        assert_eq!(DROPS.load(Relaxed), 2);
+        drop(coerced);
+        assert_eq!(DROPS.load(Relaxed), 4);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_coerce_unchecked() {
+        fn generic_fn<T: 'static>(value: &mut T) {
+            if is_same::<T, u32>() {
+                *unsafe { coerce_mut_unchecked::<T, u32>(value) } = 2;
+            }
+        }
+
+        let mut value = 1u32;
+        generic_fn(&mut value);
+        assert_eq!(value, 2);
+        assert_eq!(unsafe { *coerce_ref_unchecked::<u32, u32>(&value) }, 2);
+
+        let borrowed: &str = "coe";
+        let coerced: &str = unsafe { coerce_unchecked::<&str, &str>(borrowed) };
+        assert_eq!(coerced, "coe");
+    }
+
+    #[test]
+    fn test_coerce_phantom_data() {
+        use core::marker::PhantomData;
+
+        let marker: PhantomData<f64> = PhantomData::<f64>.coerce();
+        assert_eq!(marker, PhantomData);
+    }
+
+    #[test]
+    fn test_coerce_phantom_data_mismatch() {
+        assert_mismatch::<u32, f64>(None, || {
+            use core::marker::PhantomData;
+
+            let _: PhantomData<f64> = PhantomData::<u32>.coerce();
+        });
+    }
+
+    #[test]
+    fn test_specialize_two() {
+        fn double<T: 'static>(value: T) -> f64 {
+            specialize_two(
+                value,
+                |x: f64| 2.0 * x,
+                |x: u32| 2.0 * x as f64,
+                |_| f64::NAN,
+            )
+        }
+        assert_eq!(double(1.5f64), 3.0);
+        assert_eq!(double(2u32), 4.0);
+        assert!(double(2u8).is_nan());
+    }
+
+    #[test]
+    fn test_coerce_cell() {
+        use core::cell::{Cell, RefCell};
+
+        let cell = Cell::new(1u32);
+        let coerced: &Cell<u32> = (&cell).coerce();
+        coerced.set(2);
+        assert_eq!(cell.get(), 2);
+
+        let cell = RefCell::new(1u32);
+        let coerced: &RefCell<u32> = (&cell).coerce();
+        *coerced.borrow_mut() = 2;
+        assert_eq!(*cell.borrow(), 2);
+
+        let cell: Cell<u32> = Cell::new(3u32).coerce();
+        assert_eq!(cell.get(), 3);
+    }
+
+    #[test]
+    fn test_is_same_all() {
+        fn check<A: 'static, B: 'static, C: 'static>() -> bool {
+            is_same_all!(A, B, C)
+        }
+        assert!(check::<u32, u32, u32>());
+        assert!(!check::<u32, f64, u32>());
+        assert!(!check::<u32, u32, f64>());
+        assert!(is_same_all!(u8, u8,));
+        assert_same_all!(u8, u8, u8, u8);
+    }
+
+    #[test]
+    fn test_assert_same_all() {
+        assert_mismatch::<f64, u32>(None, || {
+            assert_same_all!(u32, u32, f64, u8);
+        });
+    }
+
+    #[test]
+    fn test_coerce_range() {
+        let range: Range<usize> = (1..4usize).coerce();
+        assert_eq!(range, 1..4);
+        let range: RangeFrom<usize> = (1usize..).coerce();
+        assert_eq!(range, 1..);
+        let range: RangeTo<usize> = (..4usize).coerce();
+        assert_eq!(range, ..4);
+
+        let mut range = 1..=2usize;
+        let coerced: RangeInclusive<usize> = range.clone().coerce();
+        assert_eq!(coerced, 1..=2);
+        range.by_ref().for_each(drop);
+        assert!(range.is_empty());
+        let coerced: RangeInclusive<usize> = range.clone().coerce();
+        assert!(coerced.is_empty());
+        assert_eq!(coerced, range);
+    }
+
+    #[test]
+    fn test_dispatch_slice() {
+        let table = [
+            SliceKernel::new::<u32>(|slice| slice.fill(1)),
+            SliceKernel::new::<f64>(|slice| slice.fill(2.0)),
+            SliceKernel::new::<f64>(|slice| slice.fill(3.0)),
+        ];
+        assert_eq!(table[1].type_id(), TypeId::of::<f64>());
+
+        let mut ints = [0u32; 2];
+        let mut floats = [0.0f64; 2];
+        let mut bytes = [0u8; 2];
+        assert!(dispatch_slice(&mut ints, &table));
+        assert!(dispatch_slice(&mut floats, &table));
+        assert!(!dispatch_slice(&mut bytes, &table));
+        assert!(!dispatch_slice(&mut ints, &[]));
+        assert_eq!(ints, [1, 1]);
+        assert_eq!(floats, [2.0, 2.0]);
+        assert_eq!(bytes, [0, 0]);
+    }
+
+    #[test]
+    fn test_coerce_wrapping_saturating() {
+        fn generic_fn<T: 'static>(wrapping: Wrapping<T>, saturating: Saturating<T>) {
+            let wrapping: Wrapping<u8> = wrapping.coerce();
+            let saturating: Saturating<u8> = saturating.coerce();
+            assert_eq!(wrapping + Wrapping(1), Wrapping(0));
+            assert_eq!(saturating + Saturating(1), Saturating(255));
+
+            let wrapping_ref: &Wrapping<u8> = (&wrapping).coerce();
+            let saturating_ref: &Saturating<u8> = (&saturating).coerce();
+            assert_eq!(*wrapping_ref - Wrapping(255), Wrapping(0));
+            assert_eq!(*saturating_ref - Saturating(255), Saturating(0));
+        }
+        generic_fn(Wrapping(255u8), Saturating(255u8));
+    }
+
+    #[test]
+    fn test_coerce_slice_of_refs() {
+        fn generic_fn<T: 'static>(refs: &[&T]) -> f64 {
+            coerce_slice_of_refs::<T, f64>(refs).iter().copied().sum()
+        }
+        let (a, b) = (1.0f64, 2.0f64);
+        assert_eq!(generic_fn(&[&a, &b, &a]), 4.0);
+    }
+
+    #[test]
+    fn test_coerce_non_null_non_zero() {
+        fn generic_fn<T: 'static>(ptr: NonNull<T>) -> NonNull<f64> {
+            ptr.coerce()
+        }
+        let mut value = 1.0f64;
+        let ptr = NonNull::from(&mut value);
+        let coerced = generic_fn(ptr);
+        assert_eq!(coerced, ptr);
+        assert_eq!(unsafe { *coerced.as_ptr() }, 1.0);
+        assert_eq!(generic_fn(NonNull::<f64>::dangling()), NonNull::dangling());
+
+        let non_zero = core::num::NonZeroU32::new(3).unwrap();
+        let coerced: core::num::NonZeroU32 = non_zero.coerce();
+        assert_eq!(coerced.get(), 3);
+        let coerced: Option<core::num::NonZeroU32> = Some(non_zero).coerce();
+        assert_eq!(coerced, Some(non_zero));
+    }
+
+    #[test]
+    fn test_coerce_static_or_else() {
+        fn to_u64<T: 'static + Into<u64>>(value: T) -> u64 {
+            coerce_static_or_else(value, Into::into)
+        }
+        assert_eq!(to_u64(u64::MAX), u64::MAX);
+        assert_eq!(to_u64(3u32), 3);
+        assert_eq!(to_u64(true), 1);
+    }
+
+    #[test]
+    fn test_coerce_fn_ptr() {
+        fn generic_fn<T: 'static>(f: fn(T) -> T, g: fn(&mut [T])) {
+            let f: fn(u32) -> u32 = f.coerce();
+            let g = coerce_fn_ptr::<T, u32>(g);
+            let mut slice = [f(1), f(2)];
+            g(&mut slice);
+            assert_eq!(slice, [3, 5]);
+        }
+        generic_fn::<u32>(|x| 2 * x, |slice| slice.iter_mut().for_each(|x| *x += 1));
+    }
+
+    #[test]
+    fn test_coerce_maybe_uninit() {
+        fn fill<T: 'static>(buf: &mut [MaybeUninit<T>]) {
+            let buf: &mut [MaybeUninit<u32>] = buf.coerce();
+            for (i, x) in buf.iter_mut().enumerate() {
+                x.write(i as u32);
+            }
+        }
+        let mut buf = [MaybeUninit::<u32>::uninit(); 3];
+        fill(&mut buf);
+        let buf = buf.map(|x| unsafe { x.assume_init() });
+        assert_eq!(buf, [0, 1, 2]);
+
+        let value: MaybeUninit<u32> = MaybeUninit::<u32>::uninit().coerce();
+        let mut value: MaybeUninit<u32> = value.coerce();
+        value.write(3);
+        assert_eq!(unsafe { value.assume_init() }, 3);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_coerce_boxed_slice() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        let empty = coerce_boxed_slice::<_, f64>(Box::<[f64]>::default());
+        assert!(empty.is_empty());
+
+        let boxed: Box<[DropCounter]> = (0..3).map(|_| DropCounter(&DROPS)).collect();
+        let ptr = boxed.as_ptr();
+        let boxed = coerce_boxed_slice::<_, DropCounter>(boxed);
+        assert_eq!((boxed.as_ptr(), boxed.len()), (ptr, 3));
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(boxed);
+        assert_eq!(DROPS.load(Relaxed), 3);
+    }
+
+    #[test]
+    fn test_coerce_pin() {
+        fn generic_fn<T: 'static>(pinned: Pin<&mut T>) -> Pin<&mut u32> {
+            pinned.coerce()
+        }
+        let mut value = 1u32;
+        let addr = &value as *const u32;
+        let mut pinned = generic_fn(Pin::new(&mut value));
+        assert_eq!(&*pinned as *const u32, addr);
+        *pinned = 2;
+        let pinned: Pin<&u32> = pinned.into_ref().coerce();
+        assert_eq!(&*pinned as *const u32, addr);
+        assert_eq!(*pinned, 2);
+    }
+
+    #[test]
+    fn test_type_key() {
+        let key = TypeKey::of::<f64>();
+        assert!(key.matches::<f64>());
+        assert!(!key.matches::<u32>());
+        assert_eq!(key, TypeKey::of::<f64>());
+        assert_ne!(key, TypeKey::of::<u32>());
+        assert_eq!(TypeKey::from(TypeId::of::<f64>()), key);
+        assert_eq!(TypeId::from(key), TypeId::of::<f64>());
+        assert!(TypeKey::of::<str>().matches::<str>());
+    }
+
+    #[test]
+    fn test_coerce_nested_refs() {
+        fn read<T: 'static>(value: &&T) -> u32 {
+            **coerce_ref_ref::<T, u32>(value)
+        }
+        fn write<T: 'static>(value: &mut &mut T) {
+            **coerce_mut_mut::<T, u32>(value) = 2;
+        }
+
+        let mut value = 1u32;
+        assert_eq!(read(&&value), 1);
+        write(&mut &mut value);
+        assert_eq!(value, 2);
+
+        static VALUE: u32 = 3;
+        let value: &'static u32 = &VALUE;
+        let coerced: &&'static u32 = (&value).coerce();
+        assert_eq!(**coerced, 3);
+    }
+
+    #[test]
+    fn test_coerce_iter() {
+        fn sum<T: 'static>(iter: impl Iterator<Item = T>) -> (usize, f64) {
+            coerce_iter::<_, f64>(iter).fold((0, 0.0), |(n, sum), x| (n + 1, sum + x))
+        }
+        assert_eq!(sum([1.0, 2.0, 3.0].iter().map(|x| 2.0 * x)), (3, 12.0));
+        assert_eq!(sum(core::iter::empty::<f64>()), (0, 0.0));
+    }
+
+    #[test]
+    fn test_coerce_iter_mismatch() {
+        assert_mismatch::<u32, f64>(None, || {
+            let _ = coerce_iter::<_, f64>(core::iter::empty::<u32>());
+        });
+    }
+
+    #[test]
+    fn test_coerce_str_ref() {
+        #[repr(transparent)]
+        struct Name(str);
+
+        fn generic_fn<T: ?Sized + 'static>(value: &T) -> usize {
+            coerce_str_ref::<T, str>(value).len()
+        }
+        assert_eq!(generic_fn("coe"), 3);
+
+        let name: &Name = unsafe { &*("coe-rs" as *const str as *const Name) };
+        let coerced: &Name = coerce_str_ref(name);
+        assert_eq!(&coerced.0, "coe-rs");
+        assert_eq!(core::mem::size_of_val(coerced), 6);
+    }
+
+    #[test]
+    fn test_coerce_str_ref_mismatch() {
+        assert_mismatch::<[u8], str>(None, || {
+            let _: &str = coerce_str_ref::<[u8], str>(b"coe");
+        });
+    }
+
+    #[test]
+    fn test_check_same() {
+        fn first<T: 'static>(slice: &[T]) -> Result<f64, TypeMismatch> {
+            check_same::<T, f64>()?;
+            Ok(coerce_slice::<T, f64>(slice)[0])
+        }
+        assert_eq!(first(&[1.0f64]), Ok(1.0));
+        assert_eq!(first(&[1u32]), Err(TypeMismatch::new::<u32, f64>()));
+        assert_eq!(check_same::<str, str>(), Ok(()));
+    }
+
+    #[test]
+    fn test_coerce_slice_of_slices() {
+        fn sum<T: 'static>(rows: &[&[T]]) -> f64 {
+            coerce_slice_of_slices::<T, f64>(rows)
+                .iter()
+                .map(|row| row.iter().sum::<f64>())
+                .sum()
+        }
+        fn clear<T: 'static>(rows: &mut [&mut [T]]) {
+            for row in coerce_slice_of_slices_mut::<T, f64>(rows) {
+                row.fill(0.0);
+            }
+        }
+
+        let (mut a, mut b) = ([1.0, 2.0, 3.0f64], [4.0f64]);
+        assert_eq!(sum(&[&a, &b, &[]]), 10.0);
+        clear(&mut [&mut a, &mut b]);
+        assert_eq!((a, b), ([0.0; 3], [0.0]));
+    }
+
+    #[test]
+    fn test_coerce_array_fn() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        assert_eq!(coerce_array::<u8, u8, 0>([]), []);
+        let large = coerce_array::<_, u64, 1024>(core::array::from_fn(|i| i as u64));
+        assert!(large.iter().enumerate().all(|(i, &x)| x == i as u64));
+
+        let counters =
+            coerce_array::<_, DropCounter, 3>(core::array::from_fn(|_| DropCounter(&DROPS)));
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(counters);
+        assert_eq!(DROPS.load(Relaxed), 3);
+    }
+
+    #[test]
+    fn test_coerce_atomic() {
+        use core::sync::atomic::{AtomicPtr, AtomicU32};
+
+        fn store<T: 'static>(atomic: &T) {
+            let atomic: &AtomicU32 = atomic.coerce();
+            atomic.store(2, Relaxed);
+        }
+        let atomic = AtomicU32::new(1);
+        store(&atomic);
+        assert_eq!(atomic.load(Relaxed), 2);
+
+        let mut value = 1u32;
+        let ptr = AtomicPtr::new(&mut value as *mut u32);
+        let coerced: &AtomicPtr<u32> = (&ptr).coerce();
+        coerced.store(core::ptr::null_mut(), Relaxed);
+        assert!(ptr.load(Relaxed).is_null());
+        let coerced: AtomicPtr<u32> = ptr.coerce();
+        assert!(coerced.into_inner().is_null());
+    }
+
+    #[test]
+    fn test_coerce_same() {
+        let slice: &[f64] = coerce_same!(&[1.0, 2.0f64][..], &[f64]);
+        assert_eq!(slice, [1.0, 2.0]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_coerce_cow() {
+        static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Debug, PartialEq)]
+        struct CloneCounter(u32);
+        impl Clone for CloneCounter {
+            fn clone(&self) -> Self {
+                CLONES.fetch_add(1, Relaxed);
+                Self(self.0)
+            }
+        }
+
+        let slice = [CloneCounter(1), CloneCounter(2)];
+        let borrowed: Cow<'_, [CloneCounter]> = Cow::Borrowed(&slice[..]).coerce();
+        assert!(matches!(borrowed, Cow::Borrowed(b) if core::ptr::eq(b, &slice[..])));
+
+        let owned = alloc::vec![CloneCounter(3)];
+        let ptr = owned.as_ptr();
+        let owned: Cow<'_, [CloneCounter]> = Cow::<[CloneCounter]>::Owned(owned).coerce();
+        assert!(matches!(&owned, Cow::Owned(o) if o.as_ptr() == ptr));
+
+        let value = CloneCounter(4);
+        let borrowed: Cow<'_, CloneCounter> = Cow::Borrowed(&value).coerce();
+        assert!(matches!(borrowed, Cow::Borrowed(b) if core::ptr::eq(b, &value)));
+        let owned: Cow<'_, CloneCounter> = Cow::<CloneCounter>::Owned(CloneCounter(5)).coerce();
+        assert_eq!(owned, Cow::<CloneCounter>::Owned(CloneCounter(5)));
+
+        assert_eq!(CLONES.load(Relaxed), 0);
+    }
+
+    #[test]
+    fn test_coerce_map() {
+        extern crate std;
+        use std::string::String;
+
+        assert_eq!(1u32.coerce_map::<u32, _>(|x| x + 1), Some(2));
+        assert_eq!(1u32.coerce_map::<f64, _>(|x| x + 1.0), None);
+        assert_eq!(String::from("coe").coerce_map(|s: String| s.len()), Some(3));
+    }
+
+    #[test]
+    fn test_same_layout() {
+        // coercions between identical types always have identical layouts, so the debug
+        // assertions never fire for them.
+        fn check<T: 'static + Default>() {
+            let _: T = coerce_static(T::default());
+            let _: Result<T, T> = try_coerce_static(T::default());
+            let _: T = unsafe { coerce_unchecked(T::default()) };
+            let value = T::default();
+            let _: &T = unsafe { coerce_ref_unchecked(&value) };
+        }
+        check::<()>();
+        check::<u8>();
+        check::<u128>();
+        check::<[f64; 7]>();
+        check::<(u8, u64, u16)>();
+    }
+
+    #[test]
+    fn test_coerce_slice_of_tuples() {
+        fn norms<T: 'static>(points: &[(T, T)]) -> [f64; 2] {
+            let points: &[(f64, f64)] = points.coerce();
+            [
+                points[0].0.hypot(points[0].1),
+                points[1].0.hypot(points[1].1),
+            ]
+        }
+        fn swap<T: 'static>(points: &mut [(T, T)]) {
+            let points: &mut [(f64, f64)] = points.coerce();
+            for (x, y) in points {
+                core::mem::swap(x, y);
+            }
+        }
+
+        let mut points = [(3.0, 4.0f64), (6.0, 8.0)];
+        assert_eq!(norms(&points), [5.0, 10.0]);
+        swap(&mut points);
+        assert_eq!(points, [(4.0, 3.0), (8.0, 6.0)]);
+    }
+
+    #[test]
+    fn test_type_name_of() {
+        assert_eq!(type_name_of::<f64>(), "f64");
+        assert_eq!(type_name_of::<[u8]>(), "[u8]");
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_derive_coerce() {
+        #[derive(Coerce, Debug, PartialEq)]
+        struct Wrapper<T>(T);
+
+        #[derive(Coerce)]
+        struct Pair<'a, A: Copy, B, const N: usize>
+        where
+            B: Clone,
+        {
+            first: &'a [A; N],
+            second: B,
+        }
+
+        #[derive(Coerce)]
+        #[repr(transparent)]
+        struct Row<T>([T]);
+
+        fn generic_fn<T: 'static>(wrapper: Wrapper<T>, row: &mut Row<T>) -> Wrapper<f64> {
+            let row: &mut Row<f64> = row.coerce();
+            row.0[0] = 3.0;
+            wrapper.coerce()
+        }
+
+        let mut data = [1.0, 2.0f64];
+        let row: &mut Row<f64> = unsafe { &mut *(&mut data[..] as *mut [f64] as *mut Row<f64>) };
+        assert_eq!(generic_fn(Wrapper(1.0), row), Wrapper(1.0));
+        let row: &Row<f64> = (&*row).coerce();
+        assert_eq!(row.0, [3.0, 2.0]);
+
+        let pair: Pair<'_, u8, u32, 2> = Pair {
+            first: &[1u8, 2],
+            second: 3u32,
+        }
+        .coerce();
+        assert_eq!((pair.first, pair.second), (&[1, 2], 3));
+    }
+
+    #[test]
+    fn test_coerce_or_default() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        assert_eq!(coerce_or_default::<u32, u32>(3), 3);
+        assert_eq!(
+            coerce_or_default::<DropCounter, u32>(DropCounter(&DROPS)),
+            0
+        );
+        assert_eq!(DROPS.load(Relaxed), 1);
+    }
+
+    #[test]
+    fn test_coerce_array_as_slice() {
+        let slice: &[u32] = coerce_array_as_slice(&[1u32, 2, 3]);
+        assert_eq!(slice, [1, 2, 3]);
+
+        let slice: &[u32] = coerce_array_as_slice::<u32, _, 0>(&[]);
+        assert!(slice.is_empty());
+
+        let slice: &[()] = coerce_array_as_slice(&[(); 4]);
+        assert_eq!(slice.len(), 4);
+    }
+
+    #[test]
+    fn test_coerce_array_as_slice_mismatch() {
+        assert_mismatch::<[u32], [f32]>(None, || {
+            let _: &[f32] = coerce_array_as_slice(&[1u32]);
+        });
+    }
+
+    #[test]
+    fn test_coerce_split_at_mut() {
+        let mut data = [1.0, 2.0, 3.0, 4.0, 5.0f64];
+        let (head, tail): (&mut [f64], &mut [f64]) = coerce_split_at_mut(&mut data[..], 2);
+        assert_eq!((head.len(), tail.len()), (2, 3));
+        head.fill(0.0);
+        tail.fill(1.0);
+        assert_eq!(data, [0.0, 0.0, 1.0, 1.0, 1.0]);
+
+        let (head, tail): (&mut [f64], &mut [f64]) = coerce_split_at_mut(&mut data[..], 5);
+        assert_eq!((head.len(), tail.len()), (5, 0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_coerce_split_at_mut_out_of_bounds() {
+        let mut data = [1.0, 2.0f64];
+        let _: (&mut [f64], &mut [f64]) = coerce_split_at_mut(&mut data[..], 3);
+    }
+
+    #[cfg(feature = "minimal_panic")]
+    #[test]
+    fn test_minimal_panic() {
+        assert_mismatch::<u32, f64>(Some("context"), || assert_same_msg::<u32, f64>("context"));
+    }
+
+    #[test]
+    fn test_coerce_nested_array() {
+        fn tile<T: 'static + Copy>(tile: [[T; 4]; 4]) -> [[f64; 4]; 4] {
+            let by_ref: &[[f64; 4]; 4] = (&tile).coerce();
+            assert_eq!(by_ref[3][3], 15.0);
+            tile.coerce()
+        }
+
+        let mut data = [[0.0f64; 4]; 4];
+        for (i, row) in data.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = (4 * i + j) as f64;
+            }
+        }
+        let tile = tile(data);
+        for (i, row) in tile.iter().enumerate() {
+            for (j, x) in row.iter().enumerate() {
+                assert_eq!(*x, (4 * i + j) as f64);
+            }
+        }
+    }
+
+    #[test]
+    fn test_specialize_dispatch() {
+        fn double_f64(slice: &mut [f64]) {
+            slice.iter_mut().for_each(|x| *x *= 2.0);
+        }
+        fn double_u32(slice: &mut [u32]) {
+            slice.iter_mut().for_each(|x| *x *= 2);
+        }
+
+        let entries = [
+            (TypeId::of::<f64>(), SliceKernel::new(double_f64)),
+            (TypeId::of::<u32>(), SliceKernel::new(double_u32)),
+        ];
+        let table = SpecializeDispatch::new(&entries);
+
+        assert_eq!(table.get::<f64>().unwrap().type_id(), TypeId::of::<f64>());
+        assert_eq!(table.get::<u32>().unwrap().type_id(), TypeId::of::<u32>());
+        assert!(table.get::<u8>().is_none());
+        assert!(table.get::<[u32]>().is_none());
+        assert_eq!(table.entries().len(), 2);
+
+        let mut data = [1, 2u32];
+        assert!(dispatch_slice(
+            &mut data,
+            core::slice::from_ref(table.get::<u32>().unwrap())
+        ));
+        assert_eq!(data, [2, 4]);
+    }
+
+    #[test]
+    fn test_as_bytes_if() {
+        assert_eq!(as_bytes_if(&[1u8, 2, 3][..]).map(<[u8]>::len), Some(3));
+        assert!(as_bytes_if(&[1u32, 2, 3][..]).is_none());
+        assert!(as_bytes_if(&[1i8, 2, 3][..]).is_none());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_coerce_pin_box() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Task {
+            state: u32,
+            _counter: DropCounter,
+        }
+
+        fn generic_fn<T: 'static>(task: Pin<Box<T>>) -> Pin<Box<Task>> {
+            task.coerce()
+        }
+
+        let task = Box::pin(Task {
+            state: 3,
+            _counter: DropCounter(&DROPS),
+        });
+        let addr = &*task as *const Task;
+        let task = generic_fn(task);
+        assert_eq!(&*task as *const Task, addr);
+        assert_eq!(task.state, 3);
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(task);
+        assert_eq!(DROPS.load(Relaxed), 1);
+    }
+
+    #[test]
+    fn test_is_type() {
+        let id = TypeId::of::<f64>();
+        assert!(is_type::<f64>(id));
+        assert!(!is_type::<u64>(id));
+
+        assert_eq!(coerce_if_type::<f64, f64>(id, 1.5), Ok(1.5));
+        assert_eq!(coerce_if_type::<u64, u64>(id, 2), Err(2));
+        assert_eq!(coerce_if_type::<f64, u64>(id, 1.5), Err(1.5));
+    }
+
+    #[test]
+    fn test_coerce_manually_drop() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        fn generic_fn<T: 'static>(value: ManuallyDrop<T>) -> ManuallyDrop<DropCounter> {
+            let by_ref: &ManuallyDrop<DropCounter> = (&value).coerce();
+            assert_eq!(by_ref.0.load(Relaxed), 0);
+            value.coerce()
+        }
+
+        {
+            let _value = generic_fn(ManuallyDrop::new(DropCounter(&DROPS)));
+        }
+        assert_eq!(DROPS.load(Relaxed), 0);
+
+        let mut value = generic_fn(ManuallyDrop::new(DropCounter(&DROPS)));
+        let inner = unsafe { ManuallyDrop::take(&mut value) };
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(inner);
+        assert_eq!(DROPS.load(Relaxed), 1);
+    }
+
+    #[test]
+    fn test_coerce2() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        fn generic_fn<T: 'static>(a: T, b: T) -> (DropCounter, DropCounter) {
+            coerce2(a, b)
+        }
+
+        let (a, b) = generic_fn(DropCounter(&DROPS), DropCounter(&DROPS));
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(a);
+        assert_eq!(DROPS.load(Relaxed), 1);
+        drop(b);
+        assert_eq!(DROPS.load(Relaxed), 2);
+
+        assert_eq!(coerce2::<f64, f64>(1.0, 2.0), (1.0, 2.0));
+    }
+
+    #[test]
+    fn test_coerce_slice_preserves_len() {
+        #[derive(Debug, PartialEq)]
+        struct Zst;
+
+        fn generic_fn<T: 'static>(slice: &mut [T]) -> (usize, usize) {
+            let len = slice.len();
+            let shared: &[Zst] = (&*slice).coerce();
+            assert_eq!(shared.len(), len);
+            let ptr = shared.as_ptr();
+            let slice: &mut [Zst] = slice.coerce();
+            assert_eq!(slice.as_ptr(), ptr);
+            (slice.len(), core::mem::size_of_val(slice))
+        }
+
+        let mut zsts = [Zst, Zst, Zst, Zst, Zst];
+        assert_eq!(generic_fn(&mut zsts), (5, 0));
+        assert_eq!(generic_fn::<Zst>(&mut []), (0, 0));
+
+        let large: &[()] =
+            unsafe { core::slice::from_raw_parts(NonNull::dangling().as_ptr(), usize::MAX) };
+        let large: &[()] = large.coerce();
+        assert_eq!(large.len(), usize::MAX);
+
+        let bytes: &[u8] = (&[1u8, 2, 3][..]).coerce();
+        assert_eq!((bytes.len(), core::mem::size_of_val(bytes)), (3, 3));
+        let words: &[u32] = (&[1u32, 2, 3][..]).coerce();
+        assert_eq!((words.len(), core::mem::size_of_val(words)), (3, 12));
+    }
+
+    #[test]
+    fn test_coerce_downcast() {
+        fn generic_fn<T: 'static>(value: &mut T) -> bool {
+            match value.coerce_downcast_mut::<f64>() {
+                Some(value) => {
+                    *value *= 2.0;
+                    true
+                }
+                None => false,
+            }
+        }
+
+        let mut float = 1.5f64;
+        let mut int = 1u32;
+        assert!(generic_fn(&mut float));
+        assert!(!generic_fn(&mut int));
+        assert_eq!(float, 3.0);
+        assert_eq!(int, 1);
+
+        assert_eq!(float.coerce_downcast_ref::<f64>(), Some(&3.0));
+        assert_eq!(int.coerce_downcast_ref::<f64>(), None);
+        assert_eq!(int.coerce_downcast_ref::<u32>(), Some(&1));
+    }
+
+    #[cfg(feature = "const_typeid")]
+    #[test]
+    fn test_assert_same_or_panic_const() {
+        fn generic_fn<T: 'static>(value: T) -> f64 {
+            assert_same_or_panic_const::<T, f64>();
+            coerce_static(value)
+        }
+
+        assert_eq!(generic_fn(1.5f64), 1.5);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_coerce_vec_in_place() {
+        fn fill<T: 'static>(scratch: &mut Vec<T>) {
+            let scratch: &mut Vec<f64> = coerce_vec_in_place(scratch);
+            scratch.clear();
+            scratch.extend([1.0, 2.0]);
+        }
+
+        let mut scratch = Vec::<f64>::with_capacity(16);
+        scratch.push(0.0);
+        let ptr = scratch.as_ptr();
+        {
+            let coerced: &mut Vec<f64> = coerce_vec_in_place(&mut scratch);
+            assert_eq!((coerced.len(), coerced.capacity()), (1, 16));
+        }
+        fill(&mut scratch);
+        assert_eq!(scratch, [1.0, 2.0]);
+        assert_eq!((scratch.as_ptr(), scratch.capacity()), (ptr, 16));
+    }
+
+    #[test]
+    fn test_coerce_from_into() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        fn generic_fn<T: 'static>(a: T, b: T) -> (f64, f64) {
+            (f64::coerce_from(a), b.coerce_into())
+        }
+
+        assert_eq!(generic_fn(1.5f64, 2.5f64), (1.5, 2.5));
+
+        let counter = DropCounter::coerce_from(DropCounter(&DROPS));
+        let counter: DropCounter = counter.coerce_into();
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(counter);
+        assert_eq!(DROPS.load(Relaxed), 1);
+    }
+
+    #[test]
+    fn test_coerce_from_mismatch() {
+        assert_mismatch::<u32, f64>(None, || {
+            let _ = f64::coerce_from(1u32);
+        });
+    }
+
+    #[test]
+    fn test_coerce_transparent() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        #[repr(transparent)]
+        struct Tagged<T>(T);
+
+        unsafe impl<T: 'static> TransparentCoerce for Tagged<T> {
+            type Inner = T;
+            type With<U: 'static> = Tagged<U>;
+        }
+
+        fn generic_fn<T: 'static>(value: Tagged<T>) -> Tagged<DropCounter> {
+            coerce_transparent(value)
+        }
+
+        let value = generic_fn(Tagged(DropCounter(&DROPS)));
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(value);
+        assert_eq!(DROPS.load(Relaxed), 1);
+
+        let value: Tagged<f64> = coerce_transparent(Tagged(1.5f64));
+        assert_eq!(value.0, 1.5);
+    }
+
+    #[test]
+    fn test_coerce_transparent_mismatch() {
+        assert_mismatch::<u32, f64>(None, || {
+            #[repr(transparent)]
+            struct Tagged<T>(T);
+
+            unsafe impl<T: 'static> TransparentCoerce for Tagged<T> {
+                type Inner = T;
+                type With<U: 'static> = Tagged<U>;
+            }
+
+            let _: Tagged<f64> = coerce_transparent(Tagged(1u32));
+        });
+    }
+
+    #[test]
+    fn test_coerce_ref_array() {
+        fn sum<T: 'static, const N: usize>(arr: &mut [T; N]) -> f64 {
+            let arr: &mut [f64; N] = coerce_mut_array(arr);
+            arr.iter_mut().for_each(|x| *x += 1.0);
+            let arr: &[f64; N] = coerce_ref_array(arr);
+            arr.iter().sum()
+        }
+
+        let mut arr = [1.0, 2.0, 3.0f64];
+        assert_eq!(sum(&mut arr), 9.0);
+        assert_eq!(arr, [2.0, 3.0, 4.0]);
+        assert_eq!(sum(&mut [0.0f64; 1]), 1.0);
+        assert_eq!(sum::<f64, 0>(&mut []), 0.0);
+
+        let arr: &[u8; 4] = coerce_ref_array(&[1u8, 2, 3, 4]);
+        assert_eq!(arr, &[1, 2, 3, 4]);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_into_from_any() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        fn round_trip<T: 'static>(value: T) -> Result<f64, Box<dyn core::any::Any>> {
+            from_any(into_any(value))
+        }
+
+        assert_eq!(round_trip(1.5f64).unwrap(), 1.5);
+        let err = round_trip(DropCounter(&DROPS)).unwrap_err();
+        assert!(err.is::<DropCounter>());
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(err);
+        assert_eq!(DROPS.load(Relaxed), 1);
+    }
+
+    #[test]
+    fn test_coerce_mut_slice_to_shared() {
+        fn generic_fn<T: 'static>(slice: &mut [T]) -> f64 {
+            let ptr = slice.as_ptr() as *const f64;
+            let shared: &[f64] = slice.coerce();
+            assert_eq!(shared.as_ptr(), ptr);
+            shared.iter().sum()
+        }
+
+        let mut data = [1.0, 2.0, 3.0f64];
+        assert_eq!(generic_fn(&mut data), 6.0);
+        data[0] = 0.0;
+        assert_eq!(generic_fn(&mut data), 5.0);
+    }
+
+    #[test]
+    fn test_is_any_of() {
+        fn index<T: 'static>() -> Option<usize> {
+            is_any_of!(T; f32, f64, u32, f64)
+        }
+
+        assert_eq!(index::<f32>(), Some(0));
+        assert_eq!(index::<f64>(), Some(1));
+        assert_eq!(index::<u32>(), Some(2));
+        assert_eq!(index::<u8>(), None);
+        assert_eq!(is_any_of!(u8; u8), Some(0));
+    }
+
+    #[test]
+    fn test_coerce_unsized_ref() {
+        #[repr(transparent)]
+        struct Name<T>([T]);
+
+        impl<T> Name<T> {
+            fn new(slice: &[T]) -> &Self {
+                unsafe { &*(slice as *const [T] as *const Self) }
+            }
+        }
+
+        fn generic_fn<T: 'static>(name: &Name<T>) -> &Name<u8> {
+            let dst: &Name<u8> = name.coerce();
+            assert!(name.try_coerce().map(|_: &Name<u16>| ()).is_err());
+            dst
+        }
+
+        let name = Name::new(b"coe");
+        let coerced = generic_fn(name);
+        assert_eq!(coerced.0.len(), 3);
+        assert_eq!(&coerced.0, b"coe");
+
+        let s: &str = coerce_ref::<str, str>("coe");
+        assert_eq!(s, "coe");
+        let mut bytes = *b"coe";
+        let bytes: &mut [u8] = coerce_mut::<[u8], [u8]>(&mut bytes);
+        assert_eq!(bytes.len(), 3);
+    }
+
+    #[test]
+    fn test_coerce_slice_if() {
+        let data = [1.0, 2.0, 3.0, 4.0f64];
+        let slice: Option<&[f64]> = coerce_slice_if(&data[..], |len| len % 2 == 0);
+        assert_eq!(slice, Some(&data[..]));
+        let slice: Option<&[f64]> = coerce_slice_if(&data[..3], |len| len % 2 == 0);
+        assert_eq!(slice, None);
+        let slice: Option<&[u64]> = coerce_slice_if(&data[..], |_| true);
+        assert_eq!(slice, None);
+
+        let mut called = false;
+        let _: Option<&[u64]> = coerce_slice_if(&data[..], |_| {
+            called = true;
+            true
+        });
+        assert!(!called);
+    }
+
+    #[test]
+    fn test_coerce_wrapper_matrix() {
+        use core::num::NonZeroU32;
+
+        macro_rules! check {
+            ($($ty: ty => $value: expr),* $(,)?) => {$({
+                fn generic_fn<T: 'static + Copy>(value: T, by_value: $ty) {
+                    let mut array = [value; 3];
+
+                    let by_ref: &$ty = (&value).coerce();
+                    assert!(by_value == *by_ref);
+
+                    let by_mut: &mut $ty = (&mut array[0]).coerce();
+                    assert!(*by_mut == by_value);
+                    let slice: &[$ty] = (&array[..]).coerce();
+                    assert!(slice.iter().all(|x| *x == by_value));
+                    let slice_mut: &mut [$ty] = (&mut array[..]).coerce();
+                    assert_eq!(slice_mut.len(), 3);
+                }
+
+                let value: $ty = $value;
+                let by_value: $ty = value.coerce();
+                generic_fn(value, by_value);
+            })*};
+        }
+
+        check!(
+            Wrapping<u8> => Wrapping(1),
+            Wrapping<i64> => Wrapping(-1),
+            Saturating<u16> => Saturating(2),
+            Saturating<i32> => Saturating(-2),
+            Reverse<u32> => Reverse(3),
+            Reverse<f64> => Reverse(3.5),
+            NonZeroU32 => NonZeroU32::new(4).unwrap(),
+            core::num::NonZeroI8 => core::num::NonZeroI8::new(-4).unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_coerce_reverse_mismatch() {
+        assert_mismatch::<u32, i32>(None, || {
+            let _: Reverse<i32> = Reverse(1u32).coerce();
+        });
+    }
+
+    #[test]
+    fn test_coerce_fn_ptr_stored_callback() {
+        struct Pass<T> {
+            callback: fn(&mut [T]),
+        }
+
+        impl<T: 'static> Pass<T> {
+            fn run_f64(&self, data: &mut [f64]) -> bool {
+                if is_same::<T, f64>() {
+                    let callback = coerce_fn_ptr::<T, f64>(self.callback);
+                    callback(data);
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+
+        let pass = Pass::<f64> {
+            callback: |slice| slice.iter_mut().for_each(|x| *x *= 2.0),
+        };
+        let mut data = [1.0, 2.0];
+        assert!(pass.run_f64(&mut data));
+        assert_eq!(data, [2.0, 4.0]);
+
+        let pass = Pass::<u32> { callback: |_| {} };
+        assert!(!pass.run_f64(&mut data));
+    }
+
+    #[cfg(not(feature = "minimal_panic"))]
+    #[test]
+    fn test_assert_same_message_names_types() {
+        extern crate std;
+        use std::string::String;
+
+        let payload = std::panic::catch_unwind(|| assert_same::<u32, [f64]>()).unwrap_err();
+        let message = payload.downcast_ref::<String>().unwrap();
+        assert!(message.contains("u32"));
+        assert!(message.contains("[f64]"));
+        assert!(!message.contains("TypeId"));
+    }
+
+    #[test]
+    fn test_coerce_exact_chunks() {
+        let data = [1.0, 2.0, 3.0, 4.0f64];
+        let chunks: Option<&[[f64; 2]]> = coerce_exact_chunks(&data[..]);
+        assert_eq!(chunks, Some(&[[1.0, 2.0], [3.0, 4.0]][..]));
+        let chunks: Option<&[[f64; 3]]> = coerce_exact_chunks(&data[..]);
+        assert_eq!(chunks, None);
+        let chunks: Option<&[[f64; 4]]> = coerce_exact_chunks(&data[..0]);
+        assert_eq!(chunks.map(<[_]>::len), Some(0));
+    }
+
+    #[test]
+    fn test_coerce_cell_witness() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        assert_eq!(CoerceCell::<u32, f64>::new(1).unwrap_err(), 1);
+
+        fn generic_fn<T: 'static>(value: T) -> f64 {
+            let mut cell = CoerceCell::<T, f64>::new(value).ok().unwrap();
+            for _ in 0..3 {
+                *cell.get_mut() *= 2.0;
+            }
+            assert_eq!(*cell.get(), 8.0);
+            let _ = cell.witness();
+            cell.into_coerced()
+        }
+        assert_eq!(generic_fn(1.0f64), 8.0);
+
+        let cell = CoerceCell::<DropCounter, DropCounter>::new(DropCounter(&DROPS))
+            .ok()
+            .unwrap();
+        assert!(core::ptr::eq(cell.get(), cell.get_inner()));
+        let counter = cell.into_inner();
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(counter);
+        assert_eq!(DROPS.load(Relaxed), 1);
+    }
+
+    #[test]
+    fn test_coerce_slice_of_options() {
+        fn generic_fn<T: 'static>(column: &mut [Option<T>]) -> u32 {
+            let column: &mut [Option<u32>] = column.coerce();
+            for x in column.iter_mut().flatten() {
+                *x += 1;
+            }
+            let column: &[Option<u32>] = column.coerce();
+            column.iter().flatten().sum()
+        }
+
+        let mut column = [Some(1u32), None, Some(3), None];
+        assert_eq!(generic_fn(&mut column), 6);
+        assert_eq!(column, [Some(2), None, Some(4), None]);
+
+        let refs = [Some(&1u8), None];
+        let refs: &[Option<&u8>] = (&refs[..]).coerce();
+        assert_eq!(refs, [Some(&1), None]);
+    }
+
+    #[test]
+    fn test_reinterpret() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        let a: f64 = coerce_static(1.5f64);
+        let b: f64 = unsafe { reinterpret(1.5f64) };
+        assert_eq!(a.to_bits(), b.to_bits());
+
+        let bits: u32 = unsafe { reinterpret(1.0f32) };
+        assert_eq!(bits, 1.0f32.to_bits());
+
+        let mut x = 1u32;
+        assert_eq!(unsafe { *reinterpret_ref::<u32, i32>(&x) }, 1);
+        unsafe { *reinterpret_mut::<u32, i32>(&mut x) = 2 };
+        assert_eq!(x, 2);
+
+        let counter: DropCounter = unsafe { reinterpret(DropCounter(&DROPS)) };
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(counter);
+        assert_eq!(DROPS.load(Relaxed), 1);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_coerce_deref_slice() {
+        fn sum<C: core::ops::Deref<Target = [T]>, T: 'static>(c: &C) -> f64 {
+            let slice: &[f64] = coerce_deref_slice(c);
+            slice.iter().sum()
+        }
+
+        let v = alloc::vec![1.0, 2.0f64];
+        let b: Box<[f64]> = Box::new([3.0, 4.0]);
+        assert_eq!(sum(&v), 3.0);
+        assert_eq!(sum(&b), 7.0);
+    }
+
+    #[test]
+    fn test_coerce_ref_or() {
+        static DEFAULT: f64 = 0.0;
+
+        fn get<T: 'static>(value: &T) -> &f64 {
+            coerce_ref_or(value, &DEFAULT)
+        }
+
+        let x = 1.5f64;
+        assert!(core::ptr::eq(get(&x), &x));
+        assert!(core::ptr::eq(get(&1u32), &DEFAULT));
+        assert_eq!(coerce_ref_or::<str, str>("coe", "default"), "coe");
+    }
+
+    #[test]
+    fn test_coerce_reverse_ordering() {
+        fn sort_desc<T: 'static>(keys: &mut [Reverse<T>]) {
+            let keys: &mut [Reverse<u32>] = keys.coerce();
+            keys.sort();
+        }
+
+        let mut keys = [Reverse(1u32), Reverse(3), Reverse(2)];
+        sort_desc(&mut keys);
+        assert_eq!(keys.map(|Reverse(x)| x), [3, 2, 1]);
+
+        let a: Reverse<u32> = Reverse(1u32).coerce();
+        let b: &Reverse<u32> = (&Reverse(2u32)).coerce();
+        assert!(a > *b);
+    }
+
+    #[test]
+    fn test_specialize_slice() {
+        fn sum_f64(slice: &mut [f64]) -> &'static str {
+            slice.iter_mut().for_each(|x| *x += 1.0);
+            "f64"
+        }
+
+        fn kernel<T: 'static>(slice: &mut [T]) -> &'static str {
+            specialize_slice!(slice: {
+                f64 => sum_f64,
+                f32 => |_: &mut [f32]| "f32",
+            });
+            let _ = slice;
+            "fallback"
+        }
+
+        let mut floats = [1.0, 2.0f64];
+        assert_eq!(kernel(&mut floats), "f64");
+        assert_eq!(floats, [2.0, 3.0]);
+        assert_eq!(kernel(&mut [1.0f32]), "f32");
+        assert_eq!(kernel(&mut [1u8]), "fallback");
+        assert_eq!(kernel::<f64>(&mut []), "f64");
+    }
+
+    #[test]
+    fn test_coerce_ref_shorten() {
+        let long = [1.0, 2.0f64];
+        let long: &[f64] = &long;
+        {
+            let short: &[f64];
+            {
+                short = coerce_ref_shorten::<[f64], [f64]>(long);
+            }
+            assert_eq!(short, [1.0, 2.0]);
+        }
+        assert_eq!(long.len(), 2);
+    }
+
+    #[test]
+    fn test_coerce_static_catch() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        assert_eq!(coerce_static_catch::<f64, f64>(1.5), Ok(1.5));
+        assert_eq!(coerce_static_catch::<u32, f64>(7), Err(7));
+
+        let counter = match coerce_static_catch::<DropCounter, u32>(DropCounter(&DROPS)) {
+            Ok(_) => unreachable!(),
+            Err(counter) => counter,
+        };
+        assert!(core::ptr::eq(counter.0, &DROPS));
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(counter);
+        assert_eq!(DROPS.load(Relaxed), 1);
+    }
+
+    #[test]
+    fn test_coerce_endianness_agnostic() {
+        fn round_trip<T: 'static + Copy>(values: &[T]) -> ([u32; 2], u64, u128) {
+            let slice: &[u32] = values.coerce();
+            let first: u64 = coerce_static((slice[0] as u64) << 32 | slice[1] as u64);
+            let wide: u128 = coerce_static(u128::from(first) << 64 | 0x0102_0304);
+            ([slice[0], slice[1]], first, wide)
+        }
+
+        let values = [0x0102_0304u32, 0xA0B0_C0D0];
+        let (slice, first, wide) = round_trip(&values);
+        assert_eq!(slice, values);
+        assert_eq!(first, 0x0102_0304_A0B0_C0D0);
+        assert_eq!(wide, 0x0102_0304_A0B0_C0D0_0000_0000_0102_0304);
+
+        // the in-memory bytes are left untouched, whatever the byte order of the target.
+        let bytes: &[[u8; 4]] = (&[[1u8, 2, 3, 4]][..]).coerce();
+        let word: &u32 = (&0x0102_0304u32).coerce();
+        assert_eq!(word.to_ne_bytes(), 0x0102_0304u32.to_ne_bytes());
+        #[cfg(target_endian = "little")]
+        assert_eq!(u32::from_ne_bytes(bytes[0]), 0x0403_0201);
+        #[cfg(target_endian = "big")]
+        assert_eq!(u32::from_ne_bytes(bytes[0]), 0x0102_0304);
+
+        let floats: &[f64] = (&[1.5f64, -0.0][..]).coerce();
+        assert_eq!(floats[0].to_bits(), 1.5f64.to_bits());
+        assert_eq!(floats[1].to_bits(), (-0.0f64).to_bits());
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_coerce_closure() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        type Kernel<T> = Box<dyn FnMut(&mut [T])>;
+
+        struct Pass<T> {
+            kernel: Kernel<T>,
+        }
+
+        fn run<T: 'static>(pass: Pass<T>, data: &mut [f64]) {
+            let mut kernel = coerce_closure::<T, f64>(pass.kernel);
+            kernel(data);
+            kernel(data);
+        }
+
+        let counter = DropCounter(&DROPS);
+        let pass = Pass::<f64> {
+            kernel: Box::new(move |slice| {
+                let _ = &counter;
+                slice.iter_mut().for_each(|x| *x += 1.0);
+            }),
+        };
+        let mut data = [1.0, 2.0];
+        run(pass, &mut data);
+        assert_eq!(data, [3.0, 4.0]);
+        assert_eq!(DROPS.load(Relaxed), 1);
+    }
+
+    #[test]
+    fn test_coerce_align_to() {
+        #[derive(Copy, Clone, Debug, PartialEq)]
+        #[repr(C, align(16))]
+        struct Lanes([u32; 4]);
+
+        #[repr(C, align(16))]
+        struct Buffer([u32; 12]);
+
+        let buffer = Buffer([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]);
+
+        let (prefix, middle, suffix) = unsafe { coerce_align_to::<u32, u32, Lanes>(&buffer.0[..]) };
+        assert!(prefix.is_empty() && suffix.is_empty());
+        assert_eq!(
+            middle,
+            [
+                Lanes([0, 1, 2, 3]),
+                Lanes([4, 5, 6, 7]),
+                Lanes([8, 9, 10, 11])
+            ]
+        );
+
+        let (prefix, middle, suffix) =
+            unsafe { coerce_align_to::<u32, u32, Lanes>(&buffer.0[1..]) };
+        assert_eq!(prefix, [1, 2, 3]);
+        assert_eq!(middle, [Lanes([4, 5, 6, 7]), Lanes([8, 9, 10, 11])]);
+        assert!(suffix.is_empty());
+
+        let (prefix, middle, suffix) =
+            unsafe { coerce_align_to::<u32, u32, Lanes>(&buffer.0[1..10]) };
+        assert_eq!(
+            (prefix, middle, suffix),
+            (&[1, 2, 3][..], &[Lanes([4, 5, 6, 7])][..], &[8, 9][..])
+        );
+    }
+
+    // `no_panic` fails to link if the function contains a reachable panic, which is only reliable
+    // with optimizations enabled. The fallible functions don't contain debug assertions either, so
+    // this also holds for debug builds.
+    #[cfg(not(debug_assertions))]
+    #[test]
+    fn test_fallible_coercions_dont_panic() {
+        use no_panic::no_panic;
+
+        #[no_panic]
+        fn try_static(value: u32) -> Result<f64, u32> {
+            try_coerce_static(value)
+        }
+
+        #[no_panic]
+        fn catch(value: [u32; 4]) -> Result<[u32; 4], [u32; 4]> {
+            coerce_static_catch(value)
+        }
+
+        #[no_panic]
+        fn try_ref(value: &[u32]) -> Result<&[f64], &[u32]> {
+            value.try_coerce()
+        }
+
+        #[no_panic]
+        fn try_mut(value: &mut u32) -> Result<&mut u32, &mut u32> {
+            value.try_coerce()
+        }
+
+        #[no_panic]
+        fn checked(value: u32) -> Result<f64, (u32, TypeMismatch)> {
+            coerce_static_checked(value)
+        }
+
+        #[no_panic]
+        fn check() -> Result<(), TypeMismatch> {
+            check_same::<u32, f64>()
+        }
+
+        #[no_panic]
+        fn unchecked(value: u32) -> u32 {
+            unsafe { coerce_unchecked(value) }
+        }
+
+        #[no_panic]
+        fn unchecked_ref(value: &[u32; 2]) -> &[u32; 2] {
+            unsafe { coerce_ref_unchecked(value) }
+        }
+
+        #[no_panic]
+        fn unchecked_mut(value: &mut [u32; 2]) -> &mut [u32; 2] {
+            unsafe { coerce_mut_unchecked(value) }
+        }
+
+        assert_eq!(try_static(1), Err(1));
+        assert_eq!(catch([1; 4]), Ok([1; 4]));
+        assert!(try_ref(&[1]).is_err());
+        assert_eq!(try_mut(&mut 1).ok(), Some(&mut 1));
+        assert_eq!(checked(1).unwrap_err().0, 1);
+        assert!(check().is_err());
+        assert_eq!(unchecked(1), 1);
+        assert_eq!(unchecked_ref(&[1, 2]), &[1, 2]);
+        assert_eq!(unchecked_mut(&mut [1, 2]), &mut [1, 2]);
+    }
+
+    #[test]
+    fn test_coerce_slice_of_unsafe_cells() {
+        use core::cell::UnsafeCell;
+
+        fn generic_fn<T: 'static>(cells: &[UnsafeCell<T>]) -> &[UnsafeCell<u32>] {
+            cells.coerce()
+        }
+
+        let cells = [UnsafeCell::new(1u32), UnsafeCell::new(2)];
+        let alias = generic_fn(&cells);
+        unsafe {
+            *cells[0].get() = 10;
+            assert_eq!(*alias[0].get(), 10);
+            *alias[1].get() = 20;
+            assert_eq!(*cells[1].get(), 20);
+        }
+        assert_eq!(alias.len(), 2);
+    }
+
+    #[test]
+    fn test_coerce_to() {
+        fn generic_fn<T: 'static>(slice: &mut [T], value: T) -> (usize, f64) {
+            slice.coerce_to::<&mut [f64]>()[0] = 3.0;
+            let first = slice.coerce_to::<&[f64]>().iter().position(|&x| x == 3.0);
+            (
+                first.unwrap(),
+                Some(value).coerce_to::<Option<f64>>().unwrap(),
+            )
+        }
+
+        let mut data = [1.0, 2.0f64];
+        assert_eq!(generic_fn(&mut data, 1.5), (0, 1.5));
+        assert_eq!(data, [3.0, 2.0]);
+    }
+
+    #[test]
+    fn test_coerce_slice_ptr() {
+        fn generic_fn<T: 'static>(ptr: *mut [T]) -> (*mut [f64], *const [f64]) {
+            (ptr.coerce(), (ptr as *const [T]).coerce())
+        }
+
+        let mut data = [1.0, 2.0, 3.0f64];
+        let ptr = &mut data[..] as *mut [f64];
+        let (ptr_mut, ptr_const) = generic_fn(ptr);
+        assert_eq!((ptr_mut.len(), ptr_const.len()), (3, 3));
+        assert_eq!(ptr_mut as *mut f64, ptr as *mut f64);
+        unsafe { (*ptr_mut)[0] = 4.0 };
+        assert_eq!(data[0], 4.0);
+
+        let null = core::ptr::slice_from_raw_parts_mut(core::ptr::null_mut::<f64>(), 5);
+        let (ptr_mut, ptr_const) = generic_fn(null);
+        assert!(ptr_mut.is_null() && ptr_const.is_null());
+        assert_eq!((ptr_mut.len(), ptr_const.len()), (5, 5));
+    }
+
+    #[test]
+    fn test_coerce_in_place() {
+        fn reset<T: 'static>(slot: &mut T) {
+            let slot: &mut [u32; 2] = coerce_in_place(slot);
+            slot[0] = 0;
+            slot[1] += 1;
+        }
+
+        let mut slot = [5u32, 6];
+        reset(&mut slot);
+        assert_eq!(slot, [0, 7]);
+    }
+
+    #[cfg(feature = "const_typeid")]
+    #[test]
+    fn test_static_assert_same() {
+        fn generic_fn<T: 'static>(value: T) -> u32 {
+            static_assert_same!(T, u32);
+            coerce_static(value)
+        }
+
+        static_assert_same!(f64, f64);
+        assert_eq!(generic_fn(1u32), 1);
+    }
+
+    #[test]
+    fn test_coerce_non_null_slice() {
+        fn generic_fn<T: 'static>(ptr: NonNull<[T]>) -> NonNull<[f64]> {
+            ptr.coerce()
+        }
+
+        let mut data = [1.0, 2.0, 3.0f64];
+        let ptr = NonNull::from(&mut data[..]);
+        let coerced = generic_fn(ptr);
+        assert_eq!(coerced.len(), 3);
+        assert_eq!(coerced.cast::<f64>(), ptr.cast::<f64>());
+        assert_eq!(unsafe { coerced.as_ref() }, [1.0, 2.0, 3.0]);
+        assert_eq!(
+            core::mem::size_of::<Option<NonNull<[f64]>>>(),
+            core::mem::size_of::<NonNull<[f64]>>()
+        );
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn test_log_coercion() {
+        extern crate std;
+        use std::string::{String, ToString};
+        use std::sync::Mutex;
+        use std::vec::Vec;
+
+        static RECORDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+        struct Logger;
+        impl log::Log for Logger {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
+            fn log(&self, record: &log::Record) {
+                RECORDS.lock().unwrap().push(record.args().to_string());
+            }
+            fn flush(&self) {}
+        }
+
+        log::set_logger(&Logger).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let _: i16 = coerce_static(1i16);
+        let _: &[i16] = (&[1i16][..]).coerce();
+        let records = RECORDS.lock().unwrap();
+        assert!(records.iter().any(|r| r == "coercing i16 into i16"));
+        assert!(records.iter().any(|r| r == "coercing [i16] into [i16]"));
+    }
+
+    #[test]
+    fn test_coerce_move_into() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        fn generic_fn<T: 'static>(src: &mut [T], dst: &mut [MaybeUninit<DropCounter>]) {
+            unsafe { coerce_move_into(src, dst) };
+        }
+
+        let mut src = ManuallyDrop::new([DropCounter(&DROPS), DropCounter(&DROPS)]);
+        let mut dst = [const { MaybeUninit::<DropCounter>::uninit() }; 2];
+        generic_fn(&mut src[..], &mut dst);
+        assert_eq!(DROPS.load(Relaxed), 0);
+
+        let dst = dst.map(|x| unsafe { x.assume_init() });
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(dst);
+        assert_eq!(DROPS.load(Relaxed), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_coerce_move_into_len_mismatch() {
+        let mut src = [1u32, 2];
+        let mut dst = [MaybeUninit::<u32>::uninit(); 3];
+        unsafe { coerce_move_into(&mut src, &mut dst) };
+    }
+
+    #[test]
+    fn test_coerce_borrow() {
+        struct View<B>(B);
+
+        impl<B: CoerceBorrow> View<B> {
+            fn into_u32(self) -> View<B::With<u32>> {
+                View(self.0.coerce_borrow())
+            }
+        }
+
+        fn generic_fn<T: 'static>(shared: &T, exclusive: &mut T) -> u32 {
+            let shared = View(shared).into_u32();
+            let exclusive = View(exclusive).into_u32();
+            *exclusive.0 += *shared.0;
+            *exclusive.0
+        }
+
+        let shared = 1u32;
+        let mut exclusive = 2u32;
+        assert_eq!(generic_fn(&shared, &mut exclusive), 3);
+        assert_eq!(exclusive, 3);
+    }
+
+    #[test]
+    fn test_coerce_borrow_mismatch() {
+        assert_mismatch::<u32, f32>(None, || {
+            let _: &mut f32 = (&mut 1u32).coerce_borrow();
+        });
+    }
+
+    #[test]
+    fn test_coerce_static_large_array() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        fn generic_fn<T: 'static>(value: T) -> [u8; 4096] {
+            coerce_static(value)
+        }
+
+        let mut array = [0u8; 4096];
+        for (i, x) in array.iter_mut().enumerate() {
+            *x = (i % 251) as u8;
+        }
+        assert_eq!(generic_fn(array), array);
+
+        let counters: [DropCounter; 3] = coerce_static([
+            DropCounter(&DROPS),
+            DropCounter(&DROPS),
+            DropCounter(&DROPS),
+        ]);
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(counters);
+        assert_eq!(DROPS.load(Relaxed), 3);
+    }
+
+    #[test]
+    fn test_coerce_array_as_slice_mut() {
+        fn fill<T: 'static, const N: usize>(arr: &mut [T; N]) -> usize {
+            let slice: &mut [f64] = coerce_array_as_slice_mut(arr);
+            slice.fill(1.0);
+            slice.len()
+        }
+
+        let mut small = [0.0f64; 1];
+        let mut large = [0.0f64; 17];
+        assert_eq!(fill(&mut small), 1);
+        assert_eq!(fill(&mut large), 17);
+        assert_eq!(fill::<f64, 0>(&mut []), 0);
+        assert!(large.iter().all(|&x| x == 1.0));
+    }
+
+    #[test]
+    fn test_const_eq() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        assert!(ConstEq::<3, 4>::new().is_none());
+        let eq = ConstEq::<3, 3>::new().unwrap();
+        assert_eq!(eq, ConstEq::<3, 3>::assert().inverse());
+
+        fn generic_fn<const N: usize>(arr: &mut [u32; N]) -> Option<u32> {
+            let eq = ConstEq::<N, 2>::new()?;
+            let arr = eq.coerce_mut(arr);
+            arr[1] += 1;
+            Some(eq.coerce_ref(eq.inverse().coerce_ref(arr))[1])
+        }
+        assert_eq!(generic_fn(&mut [1, 2]), Some(3));
+        assert_eq!(generic_fn(&mut [1, 2, 3]), None);
+
+        let counters: [DropCounter; 2] =
+            ConstEq::<2, 2>::assert().coerce([DropCounter(&DROPS), DropCounter(&DROPS)]);
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(counters);
+        assert_eq!(DROPS.load(Relaxed), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_const_eq_assert() {
+        ConstEq::<1, 2>::assert();
+    }
+
+    #[test]
+    fn test_coerce_slice_parts() {
+        fn from_ffi<T: 'static>(ptr: *mut T, len: usize) -> f64 {
+            let (ptr, len) = coerce_slice_parts_mut::<T, f64>(ptr, len);
+            let slice = unsafe { core::slice::from_raw_parts_mut(ptr, len) };
+            slice[0] = 0.0;
+            let (ptr, len) = coerce_slice_parts::<f64, f64>(slice.as_ptr(), len);
+            unsafe { core::slice::from_raw_parts(ptr, len) }
+                .iter()
+                .sum()
+        }
+
+        let mut data = [1.0, 2.0, 3.0f64];
+        assert_eq!(from_ffi(data.as_mut_ptr(), data.len()), 5.0);
+        assert_eq!(data, [0.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_coerce_as_blocks() {
+        fn block_sums<T: 'static>(arr: &[T; 8]) -> [f64; 4] {
+            let blocks: &[[f64; 2]; 4] = coerce_as_blocks(arr);
+            blocks.map(|[a, b]| a + b)
+        }
+
+        let arr = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0f64];
+        assert_eq!(block_sums(&arr), [3.0, 7.0, 11.0, 15.0]);
+
+        let whole: &[[f64; 8]; 1] = coerce_as_blocks(&arr);
+        assert_eq!(whole[0], arr);
+        let empty: &[[f64; 4]; 0] = coerce_as_blocks::<f64, f64, 0, 4, 0>(&[]);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_coerce_static_checked() {
+        assert_eq!(coerce_static_checked::<f64, f64>(1.5).ok(), Some(1.5));
+
+        let (value, err) = coerce_static_checked::<[u8; 2], u16>([1, 2]).unwrap_err();
+        assert_eq!(value, [1, 2]);
+        assert_eq!((err.found(), err.expected()), ("[u8; 2]", "u16"));
+    }
+
+    #[test]
+    fn test_coerce_chunks_iter() {
+        fn lane_sums<T: 'static>(slice: &[T]) -> [f64; 2] {
+            coerce_chunks_iter::<T, f64, 2>(slice).fold([0.0; 2], |[a, b], [x, y]| [a + x, b + y])
+        }
+
+        assert_eq!(lane_sums(&[1.0, 2.0, 3.0, 4.0, 100.0f64]), [4.0, 6.0]);
+        assert_eq!(lane_sums::<f64>(&[]), [0.0, 0.0]);
+        assert_eq!(coerce_chunks_iter::<f64, f64, 4>(&[1.0; 7]).len(), 1);
+    }
+
+    #[cfg(feature = "specialization_guard")]
+    fn sum_specialized<T: 'static + Copy + Into<f64>>(values: &[T]) -> f64 {
+        if is_same::<T, f64>() {
+            let values: &[f64] = values.coerce();
+            values.iter().sum()
+        } else {
+            values.iter().map(|&x| x.into()).sum()
+        }
+    }
+
+    #[cfg(feature = "specialization_guard")]
+    #[test]
+    fn test_specialization_guard() {
+        let outer = SpecializationGuard::<[f64], [f64]>::new();
+        {
+            let inner = SpecializationGuard::<f64, f64>::new();
+            assert_eq!(sum_specialized(&[1.0f64, 2.0]), 3.0);
+            let _: f64 = coerce_static(1.0f64);
+            assert_eq!(inner.count(), 1);
+        }
+        assert_eq!(sum_specialized(&[1.0f32, 2.0]), 3.0);
+        assert_eq!(outer.count(), 1);
+    }
+
+    #[cfg(feature = "specialization_guard")]
+    #[test]
+    #[should_panic(
+        expected = "specialization not taken: no coercion from [f64] into [f64], for the guard created at src/lib.rs"
+    )]
+    fn test_specialization_guard_missed() {
+        let _guard = SpecializationGuard::<[f64], [f64]>::new();
+        assert_eq!(sum_specialized(&[1.0f32, 2.0]), 3.0);
+    }
+
+    #[test]
+    fn test_coerce_static_table() {
+        extern crate std;
+        static TABLE: [f64; 4] = [1.0, 2.0, 4.0, 8.0];
+
+        struct Table<T: 'static>(&'static [T]);
+
+        fn table<T: 'static>(table: &'static [T]) -> Table<f64> {
+            Table(table.coerce())
+        }
+
+        static STORED: std::sync::OnceLock<Table<f64>> = std::sync::OnceLock::new();
+        let stored = STORED.get_or_init(|| table(&TABLE));
+        assert_eq!(stored.0, &TABLE[..]);
+        assert!(core::ptr::eq(stored.0, &TABLE[..]));
+    }
+
+    #[test]
+    fn test_coerce_each3() {
+        let mut input = [1.0f64, 2.0, 3.0];
+        let mut output = [0.0f64; 2];
+        let mut scratch = [0.0f64; 1];
+        let (input_ptr, output_ptr, scratch_ptr) =
+            (input.as_ptr(), output.as_ptr(), scratch.as_ptr());
+
+        let (a, b, c): (&mut [f64], &mut [f64], &mut [f64]) =
+            coerce_each3(&mut input, &mut output, &mut scratch);
+        assert_eq!((a.len(), b.len(), c.len()), (3, 2, 1));
+        assert_eq!(a.as_ptr(), input_ptr);
+        assert_eq!(b.as_ptr(), output_ptr);
+        assert_eq!(c.as_ptr(), scratch_ptr);
+        b[1] = a[2];
+        c[0] = a[0];
+        assert_eq!(output, [0.0, 3.0]);
+        assert_eq!(scratch, [1.0]);
+    }
+
+    #[test]
+    fn test_coerce_each3_mismatch() {
+        assert_mismatch::<f32, f64>(None, || {
+            let _: (&mut [f64], &mut [f64], &mut [f64]) =
+                coerce_each3(&mut [0.0f32], &mut [0.0f32], &mut [0.0f32]);
+        });
+    }
+
+    #[test]
+    fn test_coerce_concrete_from_dyn() {
+        let value = 1.5f64;
+        let erased: &dyn core::any::Any = &value;
+        let hit = coerce_concrete_from_dyn::<f64>(erased).unwrap();
+        assert!(core::ptr::eq(hit, &value));
+        assert_eq!(coerce_concrete_from_dyn::<f32>(erased), None);
+        assert_eq!(coerce_concrete_from_dyn::<&f64>(erased), None);
+    }
+
+    #[test]
+    fn test_coerce_copy() {
+        fn double<T: 'static + Copy>(x: T) -> T {
+            let x: f64 = coerce_copy(x);
+            coerce_copy(x * 2.0)
+        }
+        assert_eq!(double(1.5f64), 3.0);
+        let array: [f64; 8] = coerce_copy([1.0f64; 8]);
+        assert_eq!(array, [1.0; 8]);
+    }
+
+    #[test]
+    fn test_coerce_copy_mismatch() {
+        assert_mismatch::<f32, f64>(None, || {
+            let _: f64 = coerce_copy(1.0f32);
+        });
+    }
+
+    #[test]
+    fn test_coerce_lanes() {
+        let values: [f64; 19] = core::array::from_fn(|i| i as f64);
+        for len in [0, 3, 4, 7, 8, 9, 16, 19] {
+            let slice = &values[..len];
+
+            let (lanes, tail) = coerce_lanes::<f64, f64, 4>(slice).unwrap();
+            assert_eq!((lanes.len(), tail.len()), (len / 4, len % 4));
+            assert_eq!(lanes.as_flattened(), &slice[..len / 4 * 4]);
+            assert_eq!(tail, &slice[len / 4 * 4..]);
+
+            let (lanes, tail) = coerce_lanes::<f64, f64, 8>(slice).unwrap();
+            assert_eq!((lanes.len(), tail.len()), (len / 8, len % 8));
+            assert_eq!(lanes.as_flattened(), &slice[..len / 8 * 8]);
+            assert_eq!(tail, &slice[len / 8 * 8..]);
+
+            assert!(coerce_lanes::<f64, f32, 4>(slice).is_none());
+            assert!(coerce_lanes::<f64, u64, 8>(slice).is_none());
+        }
+    }
+
+    #[test]
+    fn test_is_same3_coerce3() {
+        fn gemm<A: 'static, B: 'static, C: 'static>(a: A, b: B, c: C) -> Option<f64> {
+            if is_same3::<A, f64, B, f64, C, f64>() {
+                let (a, b, c): (f64, f64, f64) = coerce3(a, b, c);
+                Some(a * b + c)
+            } else {
+                None
+            }
+        }
+
+        assert_eq!(gemm(2.0f64, 3.0f64, 1.0f64), Some(7.0));
+        assert_eq!(gemm(2.0f64, 3.0f32, 1.0f64), None);
+        assert_eq!(gemm(2.0f64, 3.0f64, 1u8), None);
+        assert!(is_same3::<[u8], [u8], str, str, u8, u8>());
+        assert!(!is_same3::<u8, u8, u16, u16, u32, u64>());
+    }
+
+    #[test]
+    fn test_coerce3_mismatch() {
+        assert_mismatch::<(f64, f32, u8), (f64, f64, u8)>(None, || {
+            let _: (f64, f64, u8) = coerce3(1.0f64, 1.0f32, 1u8);
+        });
+    }
+
+    #[test]
+    fn test_coerce_control_flow() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        let brk: ControlFlow<DropCounter, u32> =
+            ControlFlow::<_, u32>::Break(DropCounter(&DROPS)).coerce();
+        assert!(brk.is_break());
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(brk);
+        assert_eq!(DROPS.load(Relaxed), 1);
+
+        let cont: ControlFlow<u8, DropCounter> =
+            ControlFlow::<u8, _>::Continue(DropCounter(&DROPS)).coerce();
+        assert!(cont.is_continue());
+        assert_eq!(DROPS.load(Relaxed), 1);
+        drop(cont);
+        assert_eq!(DROPS.load(Relaxed), 2);
+
+        let cont: ControlFlow<u8, u64> = ControlFlow::<u8, u64>::Continue(7).coerce();
+        assert_eq!(cont, ControlFlow::Continue(7));
+    }
+
+    #[test]
+    fn test_coerce_control_flow_mismatch() {
+        assert_mismatch::<u8, u16>(None, || {
+            let _: ControlFlow<u16, u32> = ControlFlow::<u8, u32>::Continue(1).coerce();
+        });
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_coerce_boxed_dst() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Tagged<T: ?Sized> {
+            tag: u32,
+            tail: T,
+        }
+
+        fn generic<T: ?Sized + 'static>(b: Box<T>) -> Box<Tagged<[DropCounter]>> {
+            coerce_boxed_dst(b)
+        }
+
+        let s: Box<str> = "hello".into();
+        let ptr = s.as_ptr();
+        let s: Box<str> = coerce_boxed_dst(s);
+        assert_eq!(s.as_ptr(), ptr);
+        assert_eq!(&*s, "hello");
+
+        let tagged: Box<Tagged<[DropCounter]>> = Box::new(Tagged {
+            tag: 3,
+            tail: [DropCounter(&DROPS), DropCounter(&DROPS)],
+        });
+        let tagged = generic(tagged);
+        assert_eq!(tagged.tag, 3);
+        assert_eq!(tagged.tail.len(), 2);
+        assert_eq!(DROPS.load(Relaxed), 0);
+        drop(tagged);
+        assert_eq!(DROPS.load(Relaxed), 2);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_coerce_boxed_dst_mismatch() {
+        assert_mismatch::<str, [u8]>(None, || {
+            let s: Box<str> = "hello".into();
+            let _: Box<[u8]> = coerce_boxed_dst(s);
+        });
+    }
+
+    #[test]
+    fn test_coerce_dyn_slice() {
+        let values = [1u32, 2, 3];
+        let bytes = unsafe { core::slice::from_raw_parts(values.as_ptr().cast::<u8>(), 12) };
+
+        let tag = TypeId::of::<u32>();
+        let hit = unsafe { coerce_dyn_slice::<u32>(bytes, tag) }.unwrap();
+        assert_eq!(hit, &values);
+        assert_eq!(hit.as_ptr(), values.as_ptr());
+        assert!(unsafe { coerce_dyn_slice::<f32>(bytes, tag) }.is_none());
+        assert!(unsafe { coerce_dyn_slice::<u16>(bytes, tag) }.is_none());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "align_of")]
+    fn test_coerce_dyn_slice_misaligned() {
+        let values = [1u32, 2, 3];
+        let bytes = unsafe { core::slice::from_raw_parts(values.as_ptr().cast::<u8>(), 12) };
+        let _ = unsafe { coerce_dyn_slice::<u32>(&bytes[1..9], TypeId::of::<u32>()) };
+    }
+
+    #[test]
+    fn test_coerce_static_enum_drop_glue() {
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        #[allow(dead_code)]
+        enum Mixed {
+            Empty,
+            Plain(u64),
+            One(DropCounter),
+            Two(DropCounter, u8, DropCounter),
+            // `&'static AtomicUsize` is non-null, so `Option` uses it as a niche.
+            Niche(Option<DropCounter>),
+        }
+
+        fn roundtrip<T: 'static>(value: T) -> Mixed {
+            coerce_static(value)
+        }
+
+        let cases = [
+            (Mixed::Empty, 0),
+            (Mixed::Plain(7), 0),
+            (Mixed::One(DropCounter(&DROPS)), 1),
+            (Mixed::Two(DropCounter(&DROPS), 3, DropCounter(&DROPS)), 2),
+            (Mixed::Niche(None), 0),
+            (Mixed::Niche(Some(DropCounter(&DROPS))), 1),
+        ];
+        assert_eq!(
+            core::mem::size_of::<Option<DropCounter>>(),
+            core::mem::size_of::<DropCounter>()
+        );
+
+        for (value, drops) in cases {
+            let before = DROPS.load(Relaxed);
+            let coerced = roundtrip(value);
+            assert_eq!(DROPS.load(Relaxed), before);
+            drop(coerced);
+            assert_eq!(DROPS.load(Relaxed), before + drops);
+        }
+        assert_eq!(DROPS.load(Relaxed), 4);
+    }
+
+    #[test]
+    fn test_coerce_range_slicing() {
+        let mut values = [1.0f64, 2.0, 3.0, 4.0, 5.0];
+
+        let mid: &[f64] = coerce_range(&values, 1..4);
+        assert_eq!(mid, &[2.0, 3.0, 4.0]);
+        let tail: &[f64] = coerce_range(&values, 3..);
+        assert_eq!(tail, &[4.0, 5.0]);
+        let head: &[f64] = coerce_range(&values, ..=1);
+        assert_eq!(head, &[1.0, 2.0]);
+        let empty: &[f64] = coerce_range(&values, 2..2);
+        assert!(empty.is_empty());
+        let all: &[f64] = coerce_range(&values, ..);
+        assert_eq!(all.len(), 5);
+
+        let mid: &mut [f64] = coerce_range_mut(&mut values, 1..3);
+        mid.fill(0.0);
+        assert_eq!(values, [1.0, 0.0, 0.0, 4.0, 5.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn test_coerce_range_out_of_bounds() {
+        let _: &[f64] = coerce_range(&[1.0f64, 2.0], 1..3);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn test_coerce_range_mut_out_of_bounds() {
+        let _: &mut [f64] = coerce_range_mut(&mut [1.0f64, 2.0], 3..);
+    }
+
+    #[test]
+    fn test_coerce_range_mismatch() {
+        assert_mismatch::<[f32], [f64]>(None, || {
+            let _: &[f64] = coerce_range(&[1.0f32, 2.0], ..1);
+        });
+    }
+
+    #[test]
+    fn test_coerce_cell_slices() {
+        extern crate std;
+        use core::cell::{Cell, UnsafeCell};
+
+        struct SyncCell<T: ?Sized>(UnsafeCell<T>);
+        unsafe impl<T: ?Sized + Send> Sync for SyncCell<T> {}
+
+        fn as_f64<T: 'static>(cells: &Cell<[T]>) -> &Cell<[f64]> {
+            cells.coerce()
+        }
+
+        let mut values = [1.0f64, 2.0, 3.0];
+        let cells = as_f64(Cell::from_mut(&mut values[..]));
+        cells.as_slice_of_cells()[1].set(5.0);
+        assert_eq!(values, [1.0, 5.0, 3.0]);
+
+        fn shared<T: 'static>(cell: &SyncCell<[T]>) -> &SyncCell<[f64]> {
+            cell.coerce()
+        }
+
+        let storage = SyncCell(UnsafeCell::new([0.0f64; 4]));
+        let buffer = shared::<f64>(&storage);
+        std::thread::scope(|scope| {
+            for i in 0..4 {
+                scope.spawn(move || {
+                    let ptr = buffer.0.get() as *mut f64;
+                    // each thread writes a distinct element.
+                    unsafe { *ptr.add(i) = i as f64 };
+                });
+            }
+        });
+        assert_eq!(unsafe { &*buffer.0.get() }, &[0.0, 1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_coerce_iter_mut() {
+        fn double_rest<T: 'static>(iter: core::slice::IterMut<'_, T>) {
+            for x in coerce_iter_mut::<T, f64>(iter) {
+                *x *= 2.0;
+            }
+        }
+
+        let mut values = [1.0f64, 2.0, 3.0, 4.0];
+        let mut iter = values.iter_mut();
+        *iter.next().unwrap() = 0.0;
+        iter.next_back();
+        double_rest(iter);
+        assert_eq!(values, [0.0, 4.0, 6.0, 4.0]);
+    }
+
+    #[test]
+    fn test_coerce_wrapper() {
+        struct Strided<'a, T> {
+            data: &'a [T],
+            stride: usize,
+        }
+
+        impl<'a, T: 'static> InnerCoerce<'a> for Strided<'a, T> {
+            type Elem = T;
+            type Parts = usize;
+            type With<U: 'static> = Strided<'a, U>;
+
+            fn into_parts(self) -> (&'a [T], usize) {
+                (self.data, self.stride)
+            }
+
+            fn from_parts<U: 'static>(data: &'a [U], stride: usize) -> Strided<'a, U> {
+                Strided { data, stride }
+            }
+        }
+
+        fn sum<T: 'static>(view: Strided<'_, T>) -> f64 {
+            let view: Strided<'_, f64> = coerce_wrapper(view);
+            view.data.iter().step_by(view.stride).sum()
+        }
+
+        let data = [1.0f64, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(
+            sum(Strided {
+                data: &data,
+                stride: 2
+            }),
+            9.0
+        );
+
+        let view: Strided<'_, f64> = coerce_wrapper(Strided {
+            data: &data,
+            stride: 3,
+        });
+        assert_eq!(view.data.as_ptr(), data.as_ptr());
+        assert_eq!((view.data.len(), view.stride), (5, 3));
+    }
+
+    #[test]
+    fn test_coerce_wrapper_mismatch() {
+        assert_mismatch::<[f32], [f64]>(None, || {
+            struct View<'a, T>(&'a [T]);
+
+            impl<'a, T: 'static> InnerCoerce<'a> for View<'a, T> {
+                type Elem = T;
+                type Parts = ();
+                type With<U: 'static> = View<'a, U>;
+
+                fn into_parts(self) -> (&'a [T], ()) {
+                    (self.0, ())
+                }
+
+                fn from_parts<U: 'static>(data: &'a [U], (): ()) -> View<'a, U> {
+                    View(data)
+                }
+            }
+
+            let _: View<'_, f64> = coerce_wrapper(View(&[1.0f32]));
+        });
+    }
+
+    #[test]
+    fn test_coerce_static_or() {
+        extern crate std;
+        use std::string::{String, ToString};
+        use std::vec::Vec;
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        let mut log = Vec::new();
+        let value: u32 = coerce_static_or(DropCounter(&DROPS), |found, expected| {
+            // the original value is dropped before the callback runs.
+            assert_eq!(DROPS.load(Relaxed), 1);
+            log.push(std::format!("{found} -> {expected}"));
+            0
+        });
+        assert_eq!(value, 0);
+        assert_eq!(DROPS.load(Relaxed), 1);
+        assert_eq!(log.len(), 1);
+        assert!(log[0].ends_with("DropCounter -> u32"));
+
+        let value: String = coerce_static_or("hello".to_string(), |_, _| unreachable!());
+        assert_eq!(value, "hello");
+
+        let value: DropCounter = coerce_static_or(DropCounter(&DROPS), |_, _| unreachable!());
+        assert_eq!(DROPS.load(Relaxed), 1);
+        drop(value);
+        assert_eq!(DROPS.load(Relaxed), 2);
     }
 }
@@ -0,0 +1,25 @@
+use std::path::Path;
+use std::process::Command;
+
+/// Builds the `no_std` shared library fixture in `tests/<name>` in release mode with the given
+/// features, and returns the contents of the resulting library.
+pub fn build_fixture(name: &str, features: &[&str]) -> Vec<u8> {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join(name);
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let status = Command::new(env!("CARGO"))
+        .current_dir(&fixture)
+        .args(["build", "--release", "--quiet", "--target-dir"])
+        .arg(&target_dir)
+        .args(features.iter().flat_map(|feature| ["--features", feature]))
+        // the precompiled `core` unwinds, which `no_std` libraries can't support.
+        .env("RUSTFLAGS", "-C panic=abort")
+        .env_remove("CARGO_ENCODED_RUSTFLAGS")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let lib = format!("release/libcoe_{name}.so");
+    std::fs::read(target_dir.join(lib)).unwrap()
+}
@@ -0,0 +1,25 @@
+#![no_std]
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+fn generic<T: 'static>(value: T) -> f64 {
+    coe::coerce_static(value)
+}
+
+#[no_mangle]
+pub extern "C" fn identity(value: f64) -> f64 {
+    value
+}
+
+#[no_mangle]
+pub extern "C" fn identity_coerced(value: f64) -> f64 {
+    generic(value)
+}
+
+#[no_mangle]
+pub extern "C" fn identity_mismatched(value: f32) -> f64 {
+    generic(value)
+}
@@ -0,0 +1,62 @@
+//! Checks that a successful coercion compiles down to nothing, by building a `no_std` shared
+//! library and comparing the size of an identity function with that of the same function going
+//! through `coerce_static`.
+#![cfg(all(
+    target_os = "linux",
+    target_pointer_width = "64",
+    target_endian = "little"
+))]
+
+mod common;
+
+fn read<const N: usize>(bytes: &[u8], offset: usize) -> [u8; N] {
+    bytes[offset..offset + N].try_into().unwrap()
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> usize {
+    u16::from_le_bytes(read(bytes, offset)) as usize
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> usize {
+    u32::from_le_bytes(read(bytes, offset)) as usize
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> usize {
+    u64::from_le_bytes(read(bytes, offset)) as usize
+}
+
+// returns the size of the symbol named `name` from the symbol table of a 64-bit ELF file.
+fn symbol_size(elf: &[u8], name: &str) -> usize {
+    let (shoff, shentsize, shnum) = (
+        read_u64(elf, 0x28),
+        read_u16(elf, 0x3a),
+        read_u16(elf, 0x3c),
+    );
+    let section = |index: usize| &elf[shoff + index * shentsize..][..shentsize];
+
+    let symtab = (0..shnum)
+        .map(section)
+        .find(|section| read_u32(section, 0x04) == 2)
+        .expect("missing symbol table");
+    let strtab = section(read_u32(symtab, 0x28));
+    let (symbols, strings) = (read_u64(symtab, 0x18), read_u64(strtab, 0x18));
+
+    (0..read_u64(symtab, 0x20) / 24)
+        .map(|index| &elf[symbols + index * 24..][..24])
+        .find(|symbol| {
+            let start = strings + read_u32(symbol, 0x00);
+            let len = elf[start..].iter().position(|&byte| byte == 0).unwrap();
+            &elf[start..start + len] == name.as_bytes()
+        })
+        .map(|symbol| read_u64(symbol, 0x10))
+        .unwrap_or_else(|| panic!("missing symbol `{name}`"))
+}
+
+#[test]
+fn test_successful_coercion_is_free() {
+    let lib = common::build_fixture("hot_path", &[]);
+    let identity = symbol_size(&lib, "identity");
+    assert_eq!(symbol_size(&lib, "identity_coerced"), identity);
+    // the mismatched coercion calls the out of line panic instead.
+    assert_ne!(symbol_size(&lib, "identity_mismatched"), identity);
+}
@@ -0,0 +1,11 @@
+#![no_std]
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
+
+#[no_mangle]
+pub extern "C" fn coerce_u32_into_f32(value: u32) -> f32 {
+    coe::coerce_static(value)
+}
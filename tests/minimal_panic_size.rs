@@ -0,0 +1,20 @@
+//! Checks that failed coercions don't pull in the formatting machinery under `minimal_panic`, by
+//! building a `no_std` shared library that only contains a failing coercion and looking for
+//! `core::fmt` symbols in it.
+#![cfg(target_os = "linux")]
+
+mod common;
+
+fn links_core_fmt(features: &[&str]) -> bool {
+    let lib = common::build_fixture("minimal_panic", features);
+    // legacy mangling of `core::fmt` items, and of impls such as `<str as core::fmt::Display>`.
+    [&b"4core3fmt"[..], &b"core..fmt"[..]]
+        .iter()
+        .any(|pattern| lib.windows(pattern.len()).any(|window| window == *pattern))
+}
+
+#[test]
+fn test_minimal_panic_doesnt_link_core_fmt() {
+    assert!(links_core_fmt(&[]));
+    assert!(!links_core_fmt(&["minimal_panic"]));
+}